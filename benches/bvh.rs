@@ -0,0 +1,62 @@
+//! Rays per second through the BVH of `random_scene`, comparing the recursive
+//! tree with its linearized form.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use raytracer::bvh::{BuildOptions, LinearBvh, SplitStrategy};
+use raytracer::sampler::independent::Independent;
+use raytracer::scene::{default_camera, random_scene};
+use raytracer::{Bvh, Hittable, Interval, Ray, Sampler};
+
+const RAY_COUNT: usize = 10_000;
+
+fn camera_rays() -> Vec<Ray> {
+    let camera = default_camera().aspect_ratio(16.0 / 9.0).build();
+    let mut sampler = Independent::new(0);
+    (0..RAY_COUNT)
+        .map(|i| {
+            sampler.start_pixel_sample(i, 0, 0);
+            let (s, t) = sampler.get_2d();
+            camera.get_ray(s, t, &mut sampler)
+        })
+        .collect()
+}
+
+fn trace(world: &impl Hittable, rays: &[Ray]) -> usize {
+    rays.iter()
+        .filter(|ray| {
+            world
+                .hit(**ray, Interval::new(0.001, f64::INFINITY))
+                .is_some()
+        })
+        .count()
+}
+
+fn bench_traversal(c: &mut Criterion) {
+    let scene = random_scene(0);
+    let rays = camera_rays();
+    let median = BuildOptions {
+        strategy: SplitStrategy::Median,
+        ..BuildOptions::default()
+    };
+
+    let mut group = c.benchmark_group("random_scene");
+    group.throughput(Throughput::Elements(rays.len() as u64));
+
+    let bvh = Bvh::with_options(scene.clone(), median);
+    group.bench_function("recursive median", |b| {
+        b.iter(|| trace(black_box(&bvh), &rays))
+    });
+    let bvh = Bvh::new(scene.clone());
+    group.bench_function("recursive sah", |b| {
+        b.iter(|| trace(black_box(&bvh), &rays))
+    });
+    let linear = LinearBvh::from(&bvh);
+    group.bench_function("linear sah", |b| {
+        b.iter(|| trace(black_box(&linear), &rays))
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_traversal);
+criterion_main!(benches);
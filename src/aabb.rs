@@ -8,6 +8,12 @@ pub struct Interval {
 }
 
 impl Interval {
+    /// Contains nothing, and is the identity of `enclosing`.
+    pub const EMPTY: Interval = Interval {
+        minimum: f64::INFINITY,
+        maximum: f64::NEG_INFINITY,
+    };
+
     pub fn new(minimum: f64, maximum: f64) -> Self {
         Interval { minimum, maximum }
     }
@@ -20,7 +26,7 @@ impl Interval {
     }
 
     pub fn size(&self) -> f64 {
-        return self.maximum - self.minimum;
+        self.maximum - self.minimum
     }
 
     pub fn expand(&self, delta: f64) -> Self {
@@ -44,6 +50,12 @@ pub struct Aabb {
 }
 
 impl Aabb {
+    pub const EMPTY: Aabb = Aabb {
+        x: Interval::EMPTY,
+        y: Interval::EMPTY,
+        z: Interval::EMPTY,
+    };
+
     pub fn new(x: Interval, y: Interval, z: Interval) -> Self {
         Aabb { x, y, z }
     }
@@ -73,6 +85,23 @@ impl Aabb {
         }
     }
 
+    /// Pad the sides thinner than `delta` so that flat objects still have a
+    /// volume a ray can go through.
+    pub fn pad(&self, delta: f64) -> Self {
+        let pad_interval = |interval: Interval| {
+            if interval.size() < delta {
+                interval.expand(delta)
+            } else {
+                interval
+            }
+        };
+        Aabb {
+            x: pad_interval(self.x),
+            y: pad_interval(self.y),
+            z: pad_interval(self.z),
+        }
+    }
+
     pub fn axis(&self, n: usize) -> &Interval {
         match n {
             0 => &self.x,
@@ -82,6 +111,19 @@ impl Aabb {
         }
     }
 
+    pub fn centroid(&self) -> Point3 {
+        point!(
+            0.5 * (self.x.minimum + self.x.maximum),
+            0.5 * (self.y.minimum + self.y.maximum),
+            0.5 * (self.z.minimum + self.z.maximum)
+        )
+    }
+
+    pub fn surface_area(&self) -> f64 {
+        let (dx, dy, dz) = (self.x.size(), self.y.size(), self.z.size());
+        2.0 * (dx * dy + dy * dz + dz * dx)
+    }
+
     pub fn hit(&self, ray: Ray, mut ray_t: Interval) -> bool {
         for a in 0..3 {
             let inv_d = 1.0 / ray.direction()[a];
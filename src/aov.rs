@@ -0,0 +1,310 @@
+//! Arbitrary output variables: auxiliary images describing the surfaces seen
+//! through each pixel, used to guide denoisers and for compositing.
+//!
+//! The variables come from the first hit of the camera rays and, except for
+//! the material ID, are averaged over the samples of the pixel.
+
+use crate::film::Film;
+use crate::hit::Hit;
+use crate::image::Image;
+use crate::ray::Ray;
+use crate::tonemap;
+use crate::vec3::{Color, Point3, Vec3};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Aov {
+    /// Base color of the material, see `Material::albedo`.
+    Albedo,
+    /// Shading normal in world space, facing the camera.
+    Normal,
+    /// Distance from the camera, 0 where no surface is hit.
+    Depth,
+    /// World position, 0 where no surface is hit.
+    Position,
+    /// Index of the material, numbered from 1 in order of appearance in the
+    /// image, 0 where no surface is hit.
+    MaterialId,
+    /// Fraction of the samples hitting a surface.
+    Alpha,
+    /// Number of samples taken, which varies with adaptive sampling.
+    SampleCount,
+}
+
+impl Aov {
+    pub const ALL: [Aov; 7] = [
+        Aov::Albedo,
+        Aov::Normal,
+        Aov::Depth,
+        Aov::Position,
+        Aov::MaterialId,
+        Aov::Alpha,
+        Aov::SampleCount,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Aov::Albedo => "albedo",
+            Aov::Normal => "normal",
+            Aov::Depth => "depth",
+            Aov::Position => "position",
+            Aov::MaterialId => "material-id",
+            Aov::Alpha => "alpha",
+            Aov::SampleCount => "sample-count",
+        }
+    }
+
+    /// Make an 8 bits image of the variable that can be looked at, since most
+    /// of them are not colors.
+    pub fn preview(&self, film: &Film) -> Image {
+        let pixels = film.pixels();
+        let mut image = Image::new(film.width(), film.height());
+        let encode = |color: [f32; 3]| color.map(|c| tonemap::quantize(c as f64));
+        let (low, high) = match self {
+            Aov::Depth | Aov::Position => value_range(pixels),
+            Aov::SampleCount => (0.0, value_range(pixels).1),
+            _ => (0.0, 1.0),
+        };
+        for (index, &pixel) in pixels.iter().enumerate() {
+            let value = match self {
+                Aov::Albedo => pixel.map(|c| tonemap::srgb_encode(c as f64) as f32),
+                Aov::Normal => pixel.map(|c| 0.5 * c + 0.5),
+                Aov::Depth | Aov::Position | Aov::SampleCount => {
+                    pixel.map(|c| (c - low) / (high - low))
+                }
+                Aov::MaterialId => id_color(pixel[0] as u32),
+                Aov::Alpha => pixel,
+            };
+            image.set_pixel(index % film.width(), index / film.width(), encode(value));
+        }
+        image
+    }
+}
+
+/// Smallest and largest values of the channels, widened when they are equal.
+fn value_range(pixels: &[[f32; 3]]) -> (f32, f32) {
+    let (low, high) = pixels
+        .iter()
+        .flatten()
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(low, high), &c| {
+            (low.min(c), high.max(c))
+        });
+    if low < high {
+        (low, high)
+    } else {
+        (low, low + 1.0)
+    }
+}
+
+/// Distinct color of a material ID, black for the background.
+fn id_color(id: u32) -> [f32; 3] {
+    if id == 0 {
+        return [0.0; 3];
+    }
+    // Golden ratio steps around the hue circle keep consecutive IDs apart.
+    let hue = (id as f32 * 0.618_034).fract() * 6.0;
+    let rgb = [
+        (hue - 3.0).abs() - 1.0,
+        2.0 - (hue - 2.0).abs(),
+        2.0 - (hue - 4.0).abs(),
+    ];
+    rgb.map(|c| 0.2 + 0.8 * c.clamp(0.0, 1.0))
+}
+
+impl fmt::Display for Aov {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Debug)]
+pub struct UnknownAovError(String);
+
+impl fmt::Display for UnknownAovError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names: Vec<_> = Aov::ALL.iter().map(Aov::name).collect();
+        write!(
+            f,
+            "unknown output variable `{}`, expected one of {}",
+            self.0,
+            names.join(", ")
+        )
+    }
+}
+
+impl Error for UnknownAovError {}
+
+impl FromStr for Aov {
+    type Err = UnknownAovError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Aov::ALL
+            .into_iter()
+            .find(|aov| aov.name() == s)
+            .ok_or_else(|| UnknownAovError(s.to_string()))
+    }
+}
+
+/// Sums of the variables over the samples of a pixel.
+#[derive(Copy, Clone, Default)]
+pub(crate) struct AovSamples {
+    count: usize,
+    hits: usize,
+    albedo: Color,
+    normal: Vec3,
+    depth: f64,
+    position: Point3,
+    material: Option<usize>,
+}
+
+/// Variables of a pixel, averaged over its samples.
+#[derive(Copy, Clone)]
+pub(crate) struct AovPixel {
+    albedo: Color,
+    normal: Vec3,
+    depth: f64,
+    position: Point3,
+    alpha: f64,
+    material: Option<usize>,
+    samples: usize,
+}
+
+impl AovSamples {
+    /// Record a camera ray and its first hit, if any.
+    pub(crate) fn add(&mut self, ray: &Ray, hit: Option<&Hit>) {
+        self.count += 1;
+        if let Some(hit) = hit {
+            self.hits += 1;
+            self.albedo += hit.material.albedo(hit);
+            self.normal += hit.normal;
+            self.depth += hit.t * ray.direction().len();
+            self.position += hit.p;
+            self.material.get_or_insert(hit.material.id());
+        }
+    }
+
+    /// The state of the sums: number of samples and hits, the sums of the
+    /// albedo, normal, depth and position, and the first material hit.
+    pub(crate) fn to_raw(self) -> (usize, usize, [f64; 10], Option<usize>) {
+        let (albedo, normal, position) = (self.albedo, self.normal, self.position);
+        let sums = [
+            albedo.x(),
+            albedo.y(),
+            albedo.z(),
+            normal.x(),
+            normal.y(),
+            normal.z(),
+            self.depth,
+            position.x(),
+            position.y(),
+            position.z(),
+        ];
+        (self.count, self.hits, sums, self.material)
+    }
+
+    pub(crate) fn from_raw(
+        count: usize,
+        hits: usize,
+        sums: [f64; 10],
+        material: Option<usize>,
+    ) -> Self {
+        let [ar, ag, ab, nx, ny, nz, depth, px, py, pz] = sums;
+        AovSamples {
+            count,
+            hits,
+            albedo: color!(ar, ag, ab),
+            normal: vec3!(nx, ny, nz),
+            depth,
+            position: point!(px, py, pz),
+            material,
+        }
+    }
+
+    /// Average the variables, the pixel having taken `samples` samples in
+    /// total.
+    pub(crate) fn average(&self, samples: usize) -> AovPixel {
+        let count = self.count.max(1) as f64;
+        let hits = self.hits.max(1) as f64;
+        AovPixel {
+            albedo: self.albedo / count,
+            normal: self.normal / count,
+            depth: self.depth / hits,
+            position: self.position / hits,
+            alpha: self.hits as f64 / count,
+            material: self.material,
+            samples,
+        }
+    }
+}
+
+/// Images of the variables requested from the render.
+#[derive(Clone, Default)]
+pub struct AovFilms {
+    layers: Vec<(Aov, Film)>,
+}
+
+impl AovFilms {
+    /// Gather the requested variables of pixels stored row by row.
+    pub(crate) fn new(width: usize, height: usize, aovs: &[Aov], pixels: &[AovPixel]) -> Self {
+        let mut material_ids = HashMap::new();
+        let material_ids: Vec<_> = pixels
+            .iter()
+            .map(|pixel| match pixel.material {
+                Some(material) => {
+                    let next = material_ids.len() + 1;
+                    *material_ids.entry(material).or_insert(next) as f32
+                }
+                None => 0.0,
+            })
+            .collect();
+
+        let mut layers: Vec<(Aov, Film)> = Vec::new();
+        for &aov in aovs {
+            if layers.iter().any(|(existing, _)| *existing == aov) {
+                continue;
+            }
+            let data = pixels
+                .iter()
+                .zip(&material_ids)
+                .map(|(pixel, &id)| match aov {
+                    Aov::Albedo => to_f32(pixel.albedo),
+                    Aov::Normal => to_f32(pixel.normal),
+                    Aov::Depth => [pixel.depth as f32; 3],
+                    Aov::Position => to_f32(pixel.position),
+                    Aov::MaterialId => [id; 3],
+                    Aov::Alpha => [pixel.alpha as f32; 3],
+                    Aov::SampleCount => [pixel.samples as f32; 3],
+                })
+                .collect();
+            layers.push((aov, Film::from_raw(width, height, data)));
+        }
+        AovFilms { layers }
+    }
+
+    /// Keep only the layers of `aovs`.
+    pub(crate) fn retain(&mut self, aovs: &[Aov]) {
+        self.layers.retain(|(aov, _)| aovs.contains(aov));
+    }
+
+    pub fn get(&self, aov: Aov) -> Option<&Film> {
+        self.layers
+            .iter()
+            .find(|(existing, _)| *existing == aov)
+            .map(|(_, film)| film)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Aov, &Film)> {
+        self.layers.iter().map(|(aov, film)| (*aov, film))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+}
+
+fn to_f32(v: Vec3) -> [f32; 3] {
+    [v.x() as f32, v.y() as f32, v.z() as f32]
+}
@@ -1,95 +1,298 @@
-use std::cmp::Ordering;
-
 use crate::aabb::Interval;
+use crate::hit::Hit;
 use crate::ray::Ray;
-use crate::utils;
+use crate::vec3::Point3;
 use crate::Aabb;
 use crate::Hittable;
 use crate::HittableList;
 use std::sync::Arc;
 
-pub struct Bvh {
-    left: Arc<dyn Hittable>,
-    right: Arc<dyn Hittable>,
-    bbox: Aabb,
+pub mod linear;
+
+pub use linear::LinearBvh;
+
+/// How the objects of a node are divided between its two children.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum SplitStrategy {
+    /// Halve the objects along the widest axis of their centroids.
+    Median,
+    /// Pick the cheapest split according to the surface area heuristic,
+    /// evaluated on binned centroids along every axis.
+    #[default]
+    Sah,
+}
+
+/// Parameters of the tree construction.
+#[derive(Copy, Clone, Debug)]
+pub struct BuildOptions {
+    pub strategy: SplitStrategy,
+    /// Nodes with at most this many objects may become leaves.
+    pub max_leaf_size: usize,
 }
 
-fn box_compare(a: &Arc<dyn Hittable>, b: &Arc<dyn Hittable>, axis_index: usize) -> Ordering {
-    a.bounding_box()
-        .axis(axis_index)
-        .minimum
-        .total_cmp(&b.bounding_box().axis(axis_index).minimum)
+impl Default for BuildOptions {
+    fn default() -> Self {
+        BuildOptions {
+            strategy: SplitStrategy::default(),
+            max_leaf_size: 4,
+        }
+    }
 }
 
-fn box_x_compare(a: &Arc<dyn Hittable>, b: &Arc<dyn Hittable>) -> Ordering {
-    box_compare(a, b, 0)
+pub struct Bvh {
+    node: Node,
+    bbox: Aabb,
 }
 
-fn box_y_compare(a: &Arc<dyn Hittable>, b: &Arc<dyn Hittable>) -> Ordering {
-    box_compare(a, b, 1)
+enum Node {
+    Leaf(Vec<Arc<dyn Hittable>>),
+    Branch {
+        left: Box<Bvh>,
+        right: Box<Bvh>,
+        /// Axis along which the children were separated.
+        axis: usize,
+    },
 }
 
-fn box_z_compare(a: &Arc<dyn Hittable>, b: &Arc<dyn Hittable>) -> Ordering {
-    box_compare(a, b, 2)
+/// An object along with the values the construction needs about it.
+struct Primitive {
+    object: Arc<dyn Hittable>,
+    bbox: Aabb,
+    centroid: Point3,
 }
 
+const BIN_COUNT: usize = 16;
+/// Cost of visiting a node, relative to the cost of intersecting an object.
+const TRAVERSAL_COST: f64 = 0.125;
+/// Depth from which nodes are split at the median, which bounds the depth of
+/// the tree to this plus the logarithm of the object count.
+const SAH_MAX_DEPTH: usize = 32;
+
 impl Bvh {
     pub fn new(list: HittableList) -> Self {
-        Self::from_slice(&list.objects)
+        Self::with_options(list, BuildOptions::default())
     }
 
-    pub fn from_slice(src_objects: &[Arc<dyn Hittable>]) -> Self {
-        let mut objects = src_objects.to_vec();
-        let axis = utils::random(0..=2);
-        let comparator = match axis {
-            0 => box_x_compare,
-            1 => box_y_compare,
-            2 => box_z_compare,
-            _ => unreachable!(),
-        };
+    pub fn with_options(list: HittableList, options: BuildOptions) -> Self {
+        Self::from_slice_with(list.objects(), options)
+    }
+
+    pub fn from_slice(objects: &[Arc<dyn Hittable>]) -> Self {
+        Self::from_slice_with(objects, BuildOptions::default())
+    }
 
-        let (left, right) = if objects.len() == 1 {
-            (objects[0].clone(), objects[0].clone())
-        } else if objects.len() == 2 {
-            if comparator(&objects[0], &objects[1]) == Ordering::Less {
-                (objects[0].clone(), objects[1].clone())
-            } else {
-                (objects[1].clone(), objects[0].clone())
+    /// # Panics
+    ///
+    /// Panics if `objects` is empty.
+    pub fn from_slice_with(objects: &[Arc<dyn Hittable>], options: BuildOptions) -> Self {
+        assert!(!objects.is_empty(), "cannot build a BVH without objects");
+        let mut primitives: Vec<Primitive> = objects
+            .iter()
+            .map(|object| {
+                let bbox = object.bounding_box();
+                Primitive {
+                    object: object.clone(),
+                    bbox,
+                    centroid: bbox.centroid(),
+                }
+            })
+            .collect();
+        Self::build(&mut primitives, &options, 0)
+    }
+
+    fn build(primitives: &mut [Primitive], options: &BuildOptions, depth: usize) -> Self {
+        let bbox = primitives.iter().fold(Aabb::EMPTY, |bbox, primitive| {
+            Aabb::surrounding(&bbox, &primitive.bbox)
+        });
+        let mut options = *options;
+        if depth >= SAH_MAX_DEPTH {
+            options.strategy = SplitStrategy::Median;
+        }
+        let node = match split(primitives, &bbox, &options) {
+            Some((mid, axis)) => {
+                let (left, right) = primitives.split_at_mut(mid);
+                Node::Branch {
+                    left: Box::new(Self::build(left, &options, depth + 1)),
+                    right: Box::new(Self::build(right, &options, depth + 1)),
+                    axis,
+                }
             }
-        } else {
-            objects.sort_unstable_by(|a, b| comparator(a, b));
-            let mid = objects.len() / 2;
-            (
-                Arc::new(Bvh::from_slice(&objects[0..mid])) as Arc<dyn Hittable>,
-                Arc::new(Bvh::from_slice(&objects[mid..objects.len()])) as Arc<dyn Hittable>,
-            )
+            None => Node::Leaf(
+                primitives
+                    .iter()
+                    .map(|primitive| primitive.object.clone())
+                    .collect(),
+            ),
         };
-        let bbox = Aabb::surrounding(&left.bounding_box(), &right.bounding_box());
 
-        Bvh { left, right, bbox }
+        Bvh { node, bbox }
+    }
+}
+
+/// Reorder `primitives` so that the two children are contiguous and return
+/// the index where the second one starts along with the axis separating them,
+/// or `None` to make a leaf.
+fn split(
+    primitives: &mut [Primitive],
+    bbox: &Aabb,
+    options: &BuildOptions,
+) -> Option<(usize, usize)> {
+    let count = primitives.len();
+    if count <= 1 {
+        return None;
+    }
+
+    let centroids = primitives.iter().fold(Aabb::EMPTY, |bbox, primitive| {
+        Aabb::surrounding(
+            &bbox,
+            &Aabb::from_extremum(primitive.centroid, primitive.centroid),
+        )
+    });
+    let widest = (0..3)
+        .max_by(|&a, &b| {
+            centroids
+                .axis(a)
+                .size()
+                .total_cmp(&centroids.axis(b).size())
+        })
+        .unwrap();
+    if centroids.axis(widest).size() <= 0.0 {
+        // Every centroid is at the same place, only the count can tell the
+        // objects apart.
+        return (count > options.max_leaf_size).then_some((count / 2, widest));
+    }
+
+    match options.strategy {
+        SplitStrategy::Median => {
+            if count <= options.max_leaf_size {
+                return None;
+            }
+            let mid = count / 2;
+            primitives.select_nth_unstable_by(mid, |a, b| {
+                a.centroid[widest].total_cmp(&b.centroid[widest])
+            });
+            Some((mid, widest))
+        }
+        SplitStrategy::Sah => sah_split(primitives, bbox, &centroids, options),
+    }
+}
+
+fn bin_index(centroid: f64, extent: &Interval) -> usize {
+    let offset = (centroid - extent.minimum) / extent.size();
+    ((offset * BIN_COUNT as f64) as usize).min(BIN_COUNT - 1)
+}
+
+/// Cost of intersecting `count` objects inside `bbox`, up to the area of the
+/// parent node.
+fn side_cost(count: usize, bbox: &Aabb) -> f64 {
+    if count == 0 {
+        0.0
+    } else {
+        count as f64 * bbox.surface_area()
+    }
+}
+
+fn sah_split(
+    primitives: &mut [Primitive],
+    bbox: &Aabb,
+    centroids: &Aabb,
+    options: &BuildOptions,
+) -> Option<(usize, usize)> {
+    let count = primitives.len();
+
+    // Cost, axis, and last bin of the left child of the best split.
+    let mut best: Option<(f64, usize, usize)> = None;
+    for axis in 0..3 {
+        let extent = centroids.axis(axis);
+        if extent.size() <= 0.0 {
+            continue;
+        }
+
+        let mut bins = [(0, Aabb::EMPTY); BIN_COUNT];
+        for primitive in primitives.iter() {
+            let bin = &mut bins[bin_index(primitive.centroid[axis], extent)];
+            bin.0 += 1;
+            bin.1 = Aabb::surrounding(&bin.1, &primitive.bbox);
+        }
+
+        // Sweep from the right first so that both sides of every split are
+        // known during the second sweep.
+        let mut right_costs = [0.0; BIN_COUNT];
+        let (mut right_count, mut right_bbox) = (0, Aabb::EMPTY);
+        for bin in (1..BIN_COUNT).rev() {
+            right_count += bins[bin].0;
+            right_bbox = Aabb::surrounding(&right_bbox, &bins[bin].1);
+            right_costs[bin] = side_cost(right_count, &right_bbox);
+        }
+
+        let (mut left_count, mut left_bbox) = (0, Aabb::EMPTY);
+        for bin in 0..BIN_COUNT - 1 {
+            left_count += bins[bin].0;
+            left_bbox = Aabb::surrounding(&left_bbox, &bins[bin].1);
+            let cost = side_cost(left_count, &left_bbox) + right_costs[bin + 1];
+            if best.is_none_or(|(best_cost, _, _)| cost < best_cost) {
+                best = Some((cost, axis, bin));
+            }
+        }
+    }
+
+    let (cost, axis, last_bin) = best?;
+    let area = bbox.surface_area();
+    let cost = if area > 0.0 {
+        TRAVERSAL_COST + cost / area
+    } else {
+        TRAVERSAL_COST
+    };
+    if count <= options.max_leaf_size && cost >= count as f64 {
+        return None;
     }
+
+    let extent = centroids.axis(axis);
+    let mut mid = 0;
+    for i in 0..count {
+        if bin_index(primitives[i].centroid[axis], extent) <= last_bin {
+            primitives.swap(i, mid);
+            mid += 1;
+        }
+    }
+    Some((mid, axis))
 }
 
 impl Hittable for Bvh {
-    fn hit(&self, ray: Ray, ray_t: Interval) -> Option<crate::hit::Hit> {
+    fn hit(&self, ray: Ray, ray_t: Interval) -> Option<Hit<'_>> {
         if !self.bbox.hit(ray, ray_t) {
             return None;
         }
 
-        let hit_left = self.left.hit(ray, ray_t);
-        let hit_right = self.right.hit(
-            ray,
-            Interval::new(
-                ray_t.minimum,
-                if let Some(value) = &hit_left {
-                    value.t
-                } else {
-                    ray_t.maximum
-                },
-            ),
-        );
+        match &self.node {
+            Node::Leaf(objects) => {
+                let mut closest = None;
+                let mut closest_t = ray_t.maximum;
+                for object in objects {
+                    if let Some(hit) = object.hit(ray, Interval::new(ray_t.minimum, closest_t)) {
+                        closest_t = hit.t;
+                        closest = Some(hit);
+                    }
+                }
+                closest
+            }
+            Node::Branch { left, right, .. } => {
+                let hit_left = left.hit(ray, ray_t);
+                let hit_right = right.hit(
+                    ray,
+                    Interval::new(
+                        ray_t.minimum,
+                        if let Some(value) = &hit_left {
+                            value.t
+                        } else {
+                            ray_t.maximum
+                        },
+                    ),
+                );
 
-        hit_right.or(hit_left)
+                hit_right.or(hit_left)
+            }
+        }
     }
 
     fn bounding_box(&self) -> Aabb {
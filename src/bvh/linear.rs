@@ -0,0 +1,176 @@
+//! Bounding volume hierarchy stored in a single array, cheaper to traverse
+//! than the tree of boxed nodes.
+
+use super::{BuildOptions, Bvh, Node};
+use crate::aabb::Interval;
+use crate::hit::Hit;
+use crate::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+use crate::{Aabb, Hittable, HittableList};
+use std::sync::Arc;
+
+/// Nodes in depth-first order: the first child of an interior node directly
+/// follows it, leaves refer to a contiguous range of objects.
+pub struct LinearBvh {
+    nodes: Vec<LinearNode>,
+    objects: Vec<Arc<dyn Hittable>>,
+}
+
+#[derive(Copy, Clone)]
+struct LinearNode {
+    bbox: Aabb,
+    /// First object of a leaf, or second child of an interior node.
+    offset: u32,
+    /// Number of objects of a leaf, 0 for interior nodes.
+    count: u32,
+    /// Axis along which the children of an interior node were separated.
+    axis: u8,
+}
+
+/// Enough for the trees `Bvh` builds, whose depth is bounded.
+const STACK_SIZE: usize = 96;
+
+impl LinearBvh {
+    pub fn new(list: HittableList) -> Self {
+        Self::from(&Bvh::new(list))
+    }
+
+    pub fn with_options(list: HittableList, options: BuildOptions) -> Self {
+        Self::from(&Bvh::with_options(list, options))
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `objects` is empty.
+    pub fn from_slice(objects: &[Arc<dyn Hittable>]) -> Self {
+        Self::from(&Bvh::from_slice(objects))
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Append the nodes of `bvh` and return the index of its root.
+    fn flatten(&mut self, bvh: &Bvh) -> usize {
+        let index = self.nodes.len();
+        match &bvh.node {
+            Node::Leaf(objects) => {
+                self.nodes.push(LinearNode {
+                    bbox: bvh.bbox,
+                    offset: self.objects.len() as u32,
+                    count: objects.len() as u32,
+                    axis: 0,
+                });
+                self.objects.extend(objects.iter().cloned());
+            }
+            Node::Branch { left, right, axis } => {
+                self.nodes.push(LinearNode {
+                    bbox: bvh.bbox,
+                    offset: 0,
+                    count: 0,
+                    axis: *axis as u8,
+                });
+                self.flatten(left);
+                self.nodes[index].offset = self.flatten(right) as u32;
+            }
+        }
+        index
+    }
+}
+
+impl From<&Bvh> for LinearBvh {
+    fn from(bvh: &Bvh) -> Self {
+        let mut linear = LinearBvh {
+            nodes: Vec::new(),
+            objects: Vec::new(),
+        };
+        linear.flatten(bvh);
+        linear
+    }
+}
+
+/// Slab test with the inverse of the ray direction computed once per ray.
+fn hit_bbox(bbox: &Aabb, origin: Point3, inv_direction: Vec3, mut ray_t: Interval) -> bool {
+    for a in 0..3 {
+        let inv_d = inv_direction[a];
+        let mut t0 = (bbox.axis(a).minimum - origin[a]) * inv_d;
+        let mut t1 = (bbox.axis(a).maximum - origin[a]) * inv_d;
+        if inv_d < 0.0 {
+            std::mem::swap(&mut t0, &mut t1);
+        }
+
+        ray_t.minimum = ray_t.minimum.max(t0);
+        ray_t.maximum = ray_t.maximum.min(t1);
+        if ray_t.maximum <= ray_t.minimum {
+            return false;
+        }
+    }
+    true
+}
+
+impl Hittable for LinearBvh {
+    fn hit(&self, ray: Ray, ray_t: Interval) -> Option<Hit<'_>> {
+        let origin = ray.origin();
+        let direction = ray.direction();
+        let inv_direction = vec3!(
+            1.0 / direction.x(),
+            1.0 / direction.y(),
+            1.0 / direction.z()
+        );
+        let negative = [
+            direction.x() < 0.0,
+            direction.y() < 0.0,
+            direction.z() < 0.0,
+        ];
+
+        let mut closest = None;
+        let mut closest_t = ray_t.maximum;
+        let mut stack = [0u32; STACK_SIZE];
+        let mut stack_len = 0;
+        let mut current = 0;
+        loop {
+            let node = &self.nodes[current];
+            if hit_bbox(
+                &node.bbox,
+                origin,
+                inv_direction,
+                Interval::new(ray_t.minimum, closest_t),
+            ) {
+                if node.count > 0 {
+                    let start = node.offset as usize;
+                    for object in &self.objects[start..start + node.count as usize] {
+                        if let Some(hit) = object.hit(ray, Interval::new(ray_t.minimum, closest_t))
+                        {
+                            closest_t = hit.t;
+                            closest = Some(hit);
+                        }
+                    }
+                } else {
+                    // Visit the child closest to the ray origin first so that
+                    // the other one is more likely to be culled.
+                    let (near, far) = if negative[node.axis as usize] {
+                        (node.offset as usize, current + 1)
+                    } else {
+                        (current + 1, node.offset as usize)
+                    };
+                    stack[stack_len] = far as u32;
+                    stack_len += 1;
+                    current = near;
+                    continue;
+                }
+            }
+
+            if stack_len == 0 {
+                break;
+            }
+            stack_len -= 1;
+            current = stack[stack_len] as usize;
+        }
+
+        closest
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.nodes[0].bbox
+    }
+}
@@ -1,5 +1,5 @@
-use crate::ray::Ray;
-use crate::utils::random;
+use crate::ray::{Differentials, Ray};
+use crate::sampler::{self, Sampler};
 use crate::vec3::{cross, unit_vector, Point3, Vec3};
 
 pub struct Camera {
@@ -7,7 +7,6 @@ pub struct Camera {
     lower_left_corner: Point3,
     u: Vec3,
     v: Vec3,
-    w: Vec3,
     horizontal: Vec3,
     vertical: Vec3,
     lens_radius: f64,
@@ -15,6 +14,7 @@ pub struct Camera {
     time1: f64,
 }
 
+#[derive(Clone)]
 pub struct Builder {
     origin: Point3,
     look_at: Point3,
@@ -49,6 +49,32 @@ impl Builder {
         }
     }
 
+    pub fn look_from(&mut self, look_from: Point3) -> &mut Builder {
+        self.origin = look_from;
+        self
+    }
+
+    pub fn look_at(&mut self, look_at: Point3) -> &mut Builder {
+        self.look_at = look_at;
+        self
+    }
+
+    /// Set the vertical field of view, in degrees.
+    pub fn vfov(&mut self, vfov: f64) -> &mut Builder {
+        self.vfov = vfov;
+        self
+    }
+
+    pub fn aperture(&mut self, aperture: f64) -> &mut Builder {
+        self.lens_radius = aperture / 2.0;
+        self
+    }
+
+    pub fn focus_dist(&mut self, focus_dist: f64) -> &mut Builder {
+        self.focus_dist = focus_dist;
+        self
+    }
+
     /// Tilt a camera by changing the direction of it's upward component.
     pub fn tilted(&mut self, vup: Vec3) -> &mut Builder {
         self.vup = vup;
@@ -66,6 +92,38 @@ impl Builder {
         self
     }
 
+    /// Check that the parameters describe a camera that can be built.
+    ///
+    /// On failure, returns the name of the offending parameter, as used by
+    /// the scene files, and what is wrong with it.
+    pub fn validate(&self) -> Result<(), (&'static str, &'static str)> {
+        if !is_finite(self.origin) {
+            return Err(("look_from", "must be finite"));
+        }
+        if !is_finite(self.look_at) {
+            return Err(("look_at", "must be finite"));
+        }
+        if (self.origin - self.look_at).near_zero() {
+            return Err(("look_at", "must be different from `look_from`"));
+        }
+        if !(self.vfov > 0.0 && self.vfov < 180.0) {
+            return Err(("vfov", "must be between 0 and 180 degrees"));
+        }
+        if !(self.lens_radius.is_finite() && self.lens_radius >= 0.0) {
+            return Err(("aperture", "must not be negative"));
+        }
+        if !(self.focus_dist.is_finite() && self.focus_dist > 0.0) {
+            return Err(("focus_dist", "must be positive"));
+        }
+        if !is_finite(self.vup) || cross(self.vup, self.look_at - self.origin).near_zero() {
+            return Err((
+                "vup",
+                "must not be null or parallel to the direction from `look_from` to `look_at`",
+            ));
+        }
+        Ok(())
+    }
+
     pub fn build(&self) -> Camera {
         let h = (self.vfov.to_radians() / 2.0).tan();
         let viewport_height = 2.0 * h;
@@ -84,7 +142,6 @@ impl Builder {
             lower_left_corner,
             u,
             v,
-            w,
             horizontal,
             vertical,
             lens_radius: self.lens_radius,
@@ -94,14 +151,41 @@ impl Builder {
     }
 }
 
+fn is_finite(v: Vec3) -> bool {
+    v.x().is_finite() && v.y().is_finite() && v.z().is_finite()
+}
+
 impl Camera {
-    pub fn get_ray(&self, s: f64, t: f64) -> Ray {
-        let rd = self.lens_radius * Vec3::random_in_unit_disk();
-        let offset = self.u * rd.x() + self.v * rd.y();
-        Ray::new(
-            self.origin + offset,
-            self.lower_left_corner + s * self.horizontal + t * self.vertical - self.origin - offset,
-        )
-        .timed(random(self.time0..=self.time1))
+    /// Ray through the point `(s, t)` of the viewport, with the lens position
+    /// and the time drawn from `sampler`.
+    pub fn get_ray(&self, s: f64, t: f64, sampler: &mut dyn Sampler) -> Ray {
+        let rd = self.lens_radius * sampler::sample_disk(sampler.get_2d());
+        let time = self.time0 + (self.time1 - self.time0) * sampler.get_1d();
+        let origin = self.origin + self.u * rd.x() + self.v * rd.y();
+        Ray::new(origin, self.viewport_point(s, t) - origin).timed(time)
+    }
+
+    /// Ray through the point `(s, t)` of the viewport like `get_ray`, with
+    /// differentials through the points `ds` further right and `dt` further
+    /// up, usually the size of a pixel.
+    pub fn get_ray_differential(
+        &self,
+        s: f64,
+        t: f64,
+        (ds, dt): (f64, f64),
+        sampler: &mut dyn Sampler,
+    ) -> Ray {
+        let ray = self.get_ray(s, t, sampler);
+        let origin = ray.origin();
+        ray.with_differentials(Differentials {
+            x_origin: origin,
+            x_direction: self.viewport_point(s + ds, t) - origin,
+            y_origin: origin,
+            y_direction: self.viewport_point(s, t + dt) - origin,
+        })
+    }
+
+    fn viewport_point(&self, s: f64, t: f64) -> Point3 {
+        self.lower_left_corner + s * self.horizontal + t * self.vertical
     }
 }
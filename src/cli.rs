@@ -0,0 +1,455 @@
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use raytracer::camera;
+use raytracer::film::ExrPrecision;
+use raytracer::hdr::HdrImage;
+use raytracer::image::ImageFormat;
+use raytracer::scene::{self, SceneEntry};
+use raytracer::vec3::{Color, Point3, Vec3};
+use raytracer::Environment;
+use raytracer::{
+    AdaptiveSampling, Aov, Denoiser, OutputSettings, Progressive, RenderSettings, SamplerKind,
+    TileOrder, ToneMap,
+};
+use std::mem;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Parser)]
+#[command(version, about = "A little raytracer")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Render a scene to an image.
+    Render(Box<RenderArgs>),
+    /// List the scenes available for rendering.
+    ListScenes,
+}
+
+#[derive(Args)]
+pub struct RenderArgs {
+    /// Name of the built-in scene to render, see `list-scenes`.
+    #[arg(value_parser = parse_scene, required_unless_present = "file")]
+    pub scene: Option<&'static SceneEntry>,
+
+    /// Render the scene described by a TOML file instead of a built-in one.
+    #[arg(short, long, conflicts_with = "scene")]
+    pub file: Option<PathBuf>,
+
+    /// Path of the image to write, its extension selecting the format: 8 bits
+    /// `png` and `ppm`, or linear float `exr`, `pfm` and `hdr`.
+    #[arg(short, long, default_value = "output.png", value_parser = parse_output)]
+    pub output: PathBuf,
+
+    /// Save the samples to this file after each pass, rendering in passes of
+    /// 16 samples unless `--pass-samples` is given.
+    #[arg(long)]
+    pub checkpoint: Option<PathBuf>,
+
+    /// Resume the render saved to the checkpoint file.
+    #[arg(long, requires = "checkpoint")]
+    pub resume: bool,
+
+    /// How the progress of the render is reported.
+    #[arg(long, value_enum, default_value_t = Progress::Bar)]
+    pub progress: Progress,
+
+    #[command(flatten)]
+    pub settings: SettingsArgs,
+
+    #[command(flatten)]
+    pub camera: CameraArgs,
+
+    #[command(flatten)]
+    pub environment: EnvironmentArgs,
+
+    #[command(flatten)]
+    pub output_settings: OutputArgs,
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+pub enum Progress {
+    /// A progress bar on the terminal.
+    Bar,
+    /// One JSON object by line on the standard output for each event.
+    Json,
+    /// Nothing.
+    Silent,
+}
+
+/// Overrides applied on top of `RenderSettings::default()`.
+#[derive(Args)]
+pub struct SettingsArgs {
+    /// Width of the image in pixels.
+    #[arg(short, long, value_parser = parse_dimension)]
+    pub width: Option<usize>,
+
+    /// Ratio between the width and the height of the image.
+    #[arg(short, long, value_parser = parse_ratio)]
+    pub aspect_ratio: Option<f64>,
+
+    /// Number of rays sent through each pixel.
+    #[arg(short, long, value_parser = parse_count)]
+    pub samples: Option<usize>,
+
+    /// Keep sampling the pixels whose relative error is above this threshold,
+    /// in batches of `--samples` samples.
+    #[arg(long, value_parser = parse_threshold)]
+    pub adaptive: Option<f64>,
+
+    /// Number of samples after which adaptive sampling gives up on a pixel,
+    /// 16 times `--samples` by default. Only valid with adaptive sampling,
+    /// enabled by `--adaptive` or by the scene file.
+    #[arg(long, value_parser = parse_count)]
+    pub max_samples: Option<usize>,
+
+    /// Render in passes of this many samples, writing the image after each
+    /// one.
+    #[arg(long, value_parser = parse_count)]
+    pub pass_samples: Option<usize>,
+
+    /// Stop rendering after the pass during which this many seconds elapsed.
+    #[arg(long, value_parser = parse_seconds)]
+    pub time_budget: Option<Duration>,
+
+    /// Side of the square tiles the image is split into, in pixels.
+    #[arg(long, value_parser = parse_count)]
+    pub tile_size: Option<usize>,
+
+    /// Order in which the tiles are rendered: scanline, spiral or hilbert.
+    #[arg(long)]
+    pub tile_order: Option<TileOrder>,
+
+    /// Maximum number of bounces for a single ray.
+    #[arg(short = 'd', long, value_parser = parse_count)]
+    pub max_depth: Option<usize>,
+
+    /// Seed of the random numbers, also used by the random scenes.
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Sequence of the sample values: uniform, stratified, halton, sobol or
+    /// blue-noise.
+    #[arg(long)]
+    pub sampler: Option<SamplerKind>,
+
+    /// Filter removing the noise of the render: off or a-trous.
+    #[arg(long)]
+    pub denoiser: Option<Denoiser>,
+}
+
+/// Overrides applied on top of the camera of the scene.
+#[derive(Args)]
+pub struct CameraArgs {
+    /// Position of the camera, as `x,y,z`.
+    #[arg(long, allow_hyphen_values = true, value_parser = parse_vector)]
+    pub look_from: Option<Point3>,
+
+    /// Point the camera is looking at, as `x,y,z`.
+    #[arg(long, allow_hyphen_values = true, value_parser = parse_vector)]
+    pub look_at: Option<Point3>,
+
+    /// Upward direction of the camera, as `x,y,z`.
+    #[arg(long, allow_hyphen_values = true, value_parser = parse_vector)]
+    pub vup: Option<Vec3>,
+
+    /// Vertical field of view in degrees.
+    #[arg(long, value_parser = parse_vfov)]
+    pub vfov: Option<f64>,
+
+    /// Diameter of the lens, 0 disables the depth of field.
+    #[arg(long, value_parser = parse_aperture)]
+    pub aperture: Option<f64>,
+
+    /// Distance between the lens and the plane in focus.
+    #[arg(long, value_parser = parse_ratio)]
+    pub focus_dist: Option<f64>,
+
+    /// Shutter opening time.
+    #[arg(long, requires = "time1", allow_hyphen_values = true)]
+    pub time0: Option<f64>,
+
+    /// Shutter closing time.
+    #[arg(long, requires = "time0", allow_hyphen_values = true)]
+    pub time1: Option<f64>,
+}
+
+/// Overrides applied on top of the environment of the scene.
+#[derive(Args)]
+pub struct EnvironmentArgs {
+    /// Uniform background color, as `r,g,b`.
+    #[arg(long)]
+    pub background: Option<Color>,
+
+    /// Equirectangular Radiance HDR image lighting the scene.
+    #[arg(long, conflicts_with = "background")]
+    pub environment: Option<PathBuf>,
+
+    /// Rotation of the environment around the vertical axis, in degrees.
+    #[arg(long, allow_hyphen_values = true)]
+    pub environment_rotation: Option<f64>,
+
+    /// Multiplier applied to the light coming from the environment.
+    #[arg(long)]
+    pub environment_intensity: Option<f64>,
+}
+
+/// Overrides of how the rendered radiance is turned into an image.
+#[derive(Args)]
+pub struct OutputArgs {
+    /// Exposure compensation in stops.
+    #[arg(long, allow_hyphen_values = true)]
+    pub exposure: Option<f64>,
+
+    /// Tone mapping operator: clamp, reinhard or aces.
+    #[arg(long)]
+    pub tone_map: Option<ToneMap>,
+
+    /// Output variables to write along with the image, separated by commas:
+    /// albedo, normal, depth, position, material-id or alpha. They are layers
+    /// of OpenEXR images, and files named after the image otherwise, like
+    /// `output.normal.png`.
+    #[arg(long, value_delimiter = ',')]
+    pub aov: Vec<Aov>,
+
+    /// Format of 8 bits images, overriding the extension of the output path:
+    /// png, ppm-ascii or ppm.
+    #[arg(long)]
+    pub format: Option<ImageFormat>,
+
+    /// Store OpenEXR samples as 16 bits floats instead of 32 bits ones.
+    #[arg(long)]
+    pub half_float: bool,
+}
+
+impl SettingsArgs {
+    pub fn apply(&self, settings: &mut RenderSettings) -> Result<(), String> {
+        if self.width.is_some() || self.aspect_ratio.is_some() {
+            settings.resize(
+                self.width.unwrap_or(settings.image_width),
+                self.aspect_ratio.unwrap_or(settings.aspect_ratio),
+            );
+        }
+        if let Some(samples) = self.samples {
+            settings.sample_per_pixel = samples;
+        }
+        if let Some(threshold) = self.adaptive {
+            let max_samples = settings
+                .adaptive
+                .map_or(16 * settings.sample_per_pixel, |adaptive| {
+                    adaptive.max_samples
+                });
+            settings.adaptive = Some(AdaptiveSampling::new(threshold, max_samples));
+        }
+        if let Some(max_samples) = self.max_samples {
+            match &mut settings.adaptive {
+                Some(adaptive) => adaptive.max_samples = max_samples,
+                None => {
+                    return Err(
+                        "`--max-samples` requires adaptive sampling, enable it with `--adaptive`"
+                            .to_string(),
+                    )
+                }
+            }
+        }
+        if self.pass_samples.is_some() || self.time_budget.is_some() {
+            let progressive = settings
+                .progressive
+                .get_or_insert_with(Progressive::default);
+            if let Some(pass_samples) = self.pass_samples {
+                progressive.pass_samples = pass_samples;
+            }
+            if let Some(time_budget) = self.time_budget {
+                progressive.time_budget = Some(time_budget);
+            }
+        }
+        if let Some(tile_size) = self.tile_size {
+            settings.tile_size = tile_size;
+        }
+        if let Some(tile_order) = self.tile_order {
+            settings.tile_order = tile_order;
+        }
+        if let Some(max_depth) = self.max_depth {
+            settings.max_depth = max_depth;
+        }
+        if let Some(seed) = self.seed {
+            settings.seed = seed;
+        }
+        if let Some(sampler) = self.sampler {
+            settings.sampler = sampler;
+        }
+        if let Some(denoiser) = self.denoiser {
+            settings.denoiser = denoiser;
+        }
+        Ok(())
+    }
+}
+
+impl OutputArgs {
+    pub fn exr_precision(&self) -> ExrPrecision {
+        if self.half_float {
+            ExrPrecision::Half
+        } else {
+            ExrPrecision::Float
+        }
+    }
+
+    pub fn apply(&self, output: &mut OutputSettings) {
+        if let Some(exposure) = self.exposure {
+            output.exposure = exposure;
+        }
+        if let Some(tone_map) = self.tone_map {
+            output.tone_map = tone_map;
+        }
+    }
+}
+
+impl CameraArgs {
+    /// Apply the overrides, then check that they still describe a valid
+    /// camera together with the parameters of the scene.
+    pub fn apply(&self, builder: &mut camera::Builder) -> Result<(), String> {
+        if let Some(look_from) = self.look_from {
+            builder.look_from(look_from);
+        }
+        if let Some(look_at) = self.look_at {
+            builder.look_at(look_at);
+        }
+        if let Some(vup) = self.vup {
+            builder.tilted(vup);
+        }
+        if let Some(vfov) = self.vfov {
+            builder.vfov(vfov);
+        }
+        if let Some(aperture) = self.aperture {
+            builder.aperture(aperture);
+        }
+        if let Some(focus_dist) = self.focus_dist {
+            builder.focus_dist(focus_dist);
+        }
+        if let (Some(time0), Some(time1)) = (self.time0, self.time1) {
+            builder.timed(time0, time1);
+        }
+        builder.validate().map_err(|(field, message)| {
+            format!(
+                "invalid camera, `--{}` {}",
+                field.replace('_', "-"),
+                message
+            )
+        })
+    }
+}
+
+impl EnvironmentArgs {
+    pub fn apply(&self, environment: &mut Environment) -> Result<(), String> {
+        if let Some(color) = self.background {
+            *environment = Environment::solid(color);
+        }
+        if let Some(path) = &self.environment {
+            let image = HdrImage::load(path)
+                .map_err(|err| format!("cannot load {}: {}", path.display(), err))?;
+            *environment = Environment::map(Arc::new(image));
+        }
+        if let Some(angle) = self.environment_rotation {
+            *environment = mem::take(environment).rotated(angle);
+        }
+        if let Some(intensity) = self.environment_intensity {
+            *environment = mem::take(environment).scaled(intensity);
+        }
+        Ok(())
+    }
+}
+
+fn parse_scene(name: &str) -> Result<&'static SceneEntry, String> {
+    scene::find(name).ok_or_else(|| {
+        let names: Vec<_> = scene::SCENES.iter().map(|scene| scene.name).collect();
+        format!("unknown scene, expected one of: {}", names.join(", "))
+    })
+}
+
+/// Extensions of the image formats that can be written.
+pub const OUTPUT_EXTENSIONS: [&str; 5] = ["png", "ppm", "exr", "pfm", "hdr"];
+
+fn parse_output(value: &str) -> Result<PathBuf, String> {
+    let path = PathBuf::from(value);
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some(extension) if OUTPUT_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str()) => {
+            Ok(path)
+        }
+        _ => Err(format!(
+            "unsupported image format, expected one of: {}",
+            OUTPUT_EXTENSIONS.join(", ")
+        )),
+    }
+}
+
+fn parse_count(value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(0) => Err("must be at least 1".to_string()),
+        Ok(count) => Ok(count),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+fn parse_dimension(value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(size) if size < 2 => Err("must be at least 2 pixels".to_string()),
+        Ok(size) => Ok(size),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+fn parse_threshold(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(threshold) if threshold.is_finite() && threshold > 0.0 => Ok(threshold),
+        Ok(_) => Err("must be a positive number".to_string()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+fn parse_seconds(value: &str) -> Result<Duration, String> {
+    match value.parse::<f64>() {
+        Ok(seconds) if seconds.is_finite() && seconds > 0.0 => Ok(Duration::from_secs_f64(seconds)),
+        Ok(_) => Err("must be a positive number of seconds".to_string()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+fn parse_vector(value: &str) -> Result<Vec3, String> {
+    let vector = value.parse::<Vec3>().map_err(|err| err.to_string())?;
+    if [vector.x(), vector.y(), vector.z()]
+        .iter()
+        .all(|c| c.is_finite())
+    {
+        Ok(vector)
+    } else {
+        Err("components must be finite".to_string())
+    }
+}
+
+fn parse_vfov(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(vfov) if vfov > 0.0 && vfov < 180.0 => Ok(vfov),
+        Ok(_) => Err("must be between 0 and 180 degrees".to_string()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+fn parse_aperture(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(aperture) if aperture.is_finite() && aperture >= 0.0 => Ok(aperture),
+        Ok(_) => Err("must not be negative".to_string()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+fn parse_ratio(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(ratio) if ratio.is_finite() && ratio > 0.0 => Ok(ratio),
+        Ok(_) => Err("must be a positive number".to_string()),
+        Err(err) => Err(err.to_string()),
+    }
+}
@@ -0,0 +1,277 @@
+//! Removal of the noise left by low sample counts, as a filter run on the
+//! film once rendered.
+//!
+//! The à-trous filter follows "Edge-Avoiding À-Trous Wavelet Transform for
+//! fast Global Illumination Filtering" (Dammertz et al. 2010): a 5×5 kernel is
+//! applied several times with taps spread further apart each time, the weight
+//! of every tap falling off with the difference of the normal, albedo and
+//! depth of the pixels so that edges stay sharp. As in SVGF (Schied et al.
+//! 2017), differences of luminance are tolerated in proportion to the noise,
+//! whose variance is estimated from the neighboring pixels. The color is
+//! divided by the albedo before filtering so that textures are not blurred.
+
+use crate::aov::Aov;
+use crate::film::Film;
+use rayon::prelude::*;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Denoiser {
+    /// Keep the film as rendered.
+    #[default]
+    Off,
+    /// Edge-avoiding à-trous wavelet filter.
+    ATrous,
+}
+
+/// The output variables guiding the filter.
+pub struct Guides<'a> {
+    pub albedo: &'a Film,
+    pub normal: &'a Film,
+    pub depth: &'a Film,
+}
+
+/// Number of passes, the last one reaching 16 pixels away.
+const ITERATIONS: usize = 5;
+/// B3 spline coefficients of the kernel.
+const KERNEL: [f32; 5] = [1.0 / 16.0, 1.0 / 4.0, 3.0 / 8.0, 1.0 / 4.0, 1.0 / 16.0];
+/// Tolerated difference of luminance, in standard deviations of the noise.
+const SIGMA_LUMINANCE: f32 = 4.0;
+/// Exponent of the cosine between normals.
+const NORMAL_POWER: i32 = 64;
+const SIGMA_ALBEDO: f32 = 0.1;
+/// Tolerated difference of depth, relative to the depth of the pixel.
+const SIGMA_DEPTH: f32 = 0.05;
+/// Keeps the division by the albedo finite for black surfaces.
+const ALBEDO_EPSILON: f32 = 0.01;
+
+impl Denoiser {
+    pub const ALL: [Denoiser; 2] = [Denoiser::Off, Denoiser::ATrous];
+
+    /// Variables guiding `apply`, to render along with the film.
+    pub const GUIDES: [Aov; 3] = [Aov::Albedo, Aov::Normal, Aov::Depth];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Denoiser::Off => "off",
+            Denoiser::ATrous => "a-trous",
+        }
+    }
+
+    /// Filter `film`, whose surfaces are described by `guides`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the guides do not have the size of the film.
+    pub fn apply(&self, film: &Film, guides: &Guides) -> Film {
+        match self {
+            Denoiser::Off => film.clone(),
+            Denoiser::ATrous => a_trous(film, guides),
+        }
+    }
+}
+
+impl fmt::Display for Denoiser {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Debug)]
+pub struct UnknownDenoiserError(String);
+
+impl fmt::Display for UnknownDenoiserError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names: Vec<_> = Denoiser::ALL.iter().map(Denoiser::name).collect();
+        write!(
+            f,
+            "unknown denoiser `{}`, expected one of {}",
+            self.0,
+            names.join(", ")
+        )
+    }
+}
+
+impl Error for UnknownDenoiserError {}
+
+impl FromStr for Denoiser {
+    type Err = UnknownDenoiserError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Denoiser::ALL
+            .into_iter()
+            .find(|denoiser| denoiser.name() == s)
+            .ok_or_else(|| UnknownDenoiserError(s.to_string()))
+    }
+}
+
+fn a_trous(film: &Film, guides: &Guides) -> Film {
+    let (width, height) = (film.width(), film.height());
+    for guide in [guides.albedo, guides.normal, guides.depth] {
+        assert_eq!(
+            (guide.width(), guide.height()),
+            (width, height),
+            "guide size mismatch"
+        );
+    }
+
+    let albedo = guides.albedo.pixels();
+    let normal: Vec<_> = guides
+        .normal
+        .pixels()
+        .iter()
+        .map(|n| normalize(*n))
+        .collect();
+    let surfaces = Surfaces {
+        width,
+        height,
+        albedo,
+        normal: &normal,
+        depth: guides.depth.pixels(),
+    };
+
+    let irradiance: Vec<[f32; 3]> = film
+        .pixels()
+        .iter()
+        .zip(albedo)
+        .map(|(color, albedo)| [0, 1, 2].map(|i| color[i] / albedo[i].max(ALBEDO_EPSILON)))
+        .collect();
+    let mut variance = surfaces.estimate_variance(&irradiance);
+    let mut irradiance = irradiance;
+
+    for iteration in 0..ITERATIONS {
+        let step = 1 << iteration;
+        let blurred = surfaces.blur(&variance);
+        let (next_irradiance, next_variance) = (0..width * height)
+            .into_par_iter()
+            .map(|p| {
+                let luminance_p = luminance(irradiance[p]);
+                let tolerance = SIGMA_LUMINANCE * blurred[p].sqrt() + f32::EPSILON;
+                let mut sum = [0.0; 3];
+                let mut sum_variance = 0.0;
+                let mut weights = 0.0;
+                surfaces.for_each_tap(p, step, |q, kernel, geometry| {
+                    let luminance = (luminance_p - luminance(irradiance[q])).abs() / tolerance;
+                    let weight = kernel * geometry * (-luminance).exp();
+                    for c in 0..3 {
+                        sum[c] += weight * irradiance[q][c];
+                    }
+                    sum_variance += weight * weight * variance[q];
+                    weights += weight;
+                });
+                // The center tap always has a positive weight.
+                (sum.map(|c| c / weights), sum_variance / (weights * weights))
+            })
+            .unzip();
+        irradiance = next_irradiance;
+        variance = next_variance;
+    }
+
+    let data = irradiance
+        .iter()
+        .zip(albedo)
+        .map(|(irradiance, albedo)| {
+            [0, 1, 2].map(|i| irradiance[i] * albedo[i].max(ALBEDO_EPSILON))
+        })
+        .collect();
+    Film::from_raw(width, height, data)
+}
+
+/// The guides of the filter, telling which pixels see the same surface.
+struct Surfaces<'a> {
+    width: usize,
+    height: usize,
+    albedo: &'a [[f32; 3]],
+    normal: &'a [Option<[f32; 3]>],
+    depth: &'a [[f32; 3]],
+}
+
+impl Surfaces<'_> {
+    /// Call `f` with the index, kernel weight, and similarity of the surfaces
+    /// of the taps around pixel `p` spaced by `step`.
+    fn for_each_tap(&self, p: usize, step: isize, mut f: impl FnMut(usize, f32, f32)) {
+        let (x, y) = ((p % self.width) as isize, (p / self.width) as isize);
+        for (j, ky) in KERNEL.iter().enumerate() {
+            let qy = y + (j as isize - 2) * step;
+            if qy < 0 || qy >= self.height as isize {
+                continue;
+            }
+            for (i, kx) in KERNEL.iter().enumerate() {
+                let qx = x + (i as isize - 2) * step;
+                if qx < 0 || qx >= self.width as isize {
+                    continue;
+                }
+                let q = qy as usize * self.width + qx as usize;
+                f(q, kx * ky, self.similarity(p, q));
+            }
+        }
+    }
+
+    fn similarity(&self, p: usize, q: usize) -> f32 {
+        let normal = match (self.normal[p], self.normal[q]) {
+            (Some(n_p), Some(n_q)) => dot(n_p, n_q).max(0.0).powi(NORMAL_POWER),
+            (None, None) => 1.0,
+            _ => 0.0,
+        };
+        let albedo =
+            distance_squared(self.albedo[p], self.albedo[q]) / (SIGMA_ALBEDO * SIGMA_ALBEDO);
+        let (depth_p, depth_q) = (self.depth[p][0], self.depth[q][0]);
+        let depth = (depth_p - depth_q).abs() / (SIGMA_DEPTH * depth_p.max(depth_q) + f32::EPSILON);
+        normal * (-(albedo + depth)).exp()
+    }
+
+    /// Variance of the luminance of each pixel, estimated from the
+    /// neighboring pixels seeing the same surface.
+    fn estimate_variance(&self, irradiance: &[[f32; 3]]) -> Vec<f32> {
+        (0..self.width * self.height)
+            .into_par_iter()
+            .map(|p| {
+                let (mut moment_1, mut moment_2, mut weights) = (0.0, 0.0, 0.0);
+                self.for_each_tap(p, 1, |q, _, geometry| {
+                    let luminance = luminance(irradiance[q]);
+                    moment_1 += geometry * luminance;
+                    moment_2 += geometry * luminance * luminance;
+                    weights += geometry;
+                });
+                let mean = moment_1 / weights;
+                (moment_2 / weights - mean * mean).max(0.0)
+            })
+            .collect()
+    }
+
+    /// Smooth the variance with the kernel, making the tolerance of the
+    /// luminance differences more reliable.
+    fn blur(&self, variance: &[f32]) -> Vec<f32> {
+        (0..self.width * self.height)
+            .into_par_iter()
+            .map(|p| {
+                let (mut sum, mut weights) = (0.0, 0.0);
+                self.for_each_tap(p, 1, |q, kernel, _| {
+                    sum += kernel * variance[q];
+                    weights += kernel;
+                });
+                sum / weights
+            })
+            .collect()
+    }
+}
+
+fn luminance([r, g, b]: [f32; 3]) -> f32 {
+    0.2126 * r + 0.7152 * g + 0.0722 * b
+}
+
+fn distance_squared(a: [f32; 3], b: [f32; 3]) -> f32 {
+    (0..3).map(|i| (a[i] - b[i]) * (a[i] - b[i])).sum()
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+/// Unit length normal, none where no surface was hit.
+fn normalize(n: [f32; 3]) -> Option<[f32; 3]> {
+    let length = dot(n, n).sqrt();
+    (length > 1e-6).then(|| n.map(|c| c / length))
+}
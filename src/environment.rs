@@ -0,0 +1,103 @@
+use crate::hdr::HdrImage;
+use crate::vec3::{unit_vector, Color, Vec3};
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+/// Radiance coming from every direction not blocked by the scene.
+#[derive(Clone)]
+pub struct Environment {
+    sky: Sky,
+    rotation: f64,
+    intensity: f64,
+}
+
+#[derive(Clone)]
+enum Sky {
+    Solid(Color),
+    Gradient {
+        bottom: Color,
+        top: Color,
+    },
+    /// Equirectangular map, the top row looking up and the center of the image
+    /// looking towards `-z`.
+    Map(Arc<HdrImage>),
+}
+
+impl Environment {
+    fn with_sky(sky: Sky) -> Self {
+        Environment {
+            sky,
+            rotation: 0.0,
+            intensity: 1.0,
+        }
+    }
+
+    pub fn solid(color: Color) -> Self {
+        Self::with_sky(Sky::Solid(color))
+    }
+
+    /// Vertical gradient from the color looking down to the one looking up.
+    pub fn gradient(bottom: Color, top: Color) -> Self {
+        Self::with_sky(Sky::Gradient { bottom, top })
+    }
+
+    pub fn map(image: Arc<HdrImage>) -> Self {
+        Self::with_sky(Sky::Map(image))
+    }
+
+    /// Rotate the environment around the vertical axis, angle in degrees.
+    pub fn rotated(mut self, angle: f64) -> Self {
+        self.rotation = angle.to_radians();
+        self
+    }
+
+    /// Multiply the radiance of the environment.
+    pub fn scaled(mut self, intensity: f64) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    pub fn value(&self, direction: Vec3) -> Color {
+        let direction = unit_vector(direction);
+        let color = match &self.sky {
+            Sky::Solid(color) => *color,
+            Sky::Gradient { bottom, top } => {
+                let t = 0.5 * (direction.y() + 1.0);
+                (1.0 - t) * *bottom + t * *top
+            }
+            Sky::Map(image) => {
+                let (sin, cos) = self.rotation.sin_cos();
+                let x = cos * direction.x() + sin * direction.z();
+                let z = -sin * direction.x() + cos * direction.z();
+                let u = 0.5 + f64::atan2(x, -z) / (2.0 * PI);
+                let v = direction.y().clamp(-1.0, 1.0).acos() / PI;
+                sample_bilinear(image, u, v)
+            }
+        };
+        self.intensity * color
+    }
+}
+
+/// The white to light blue sky of the original renderer.
+impl Default for Environment {
+    fn default() -> Self {
+        Environment::gradient(color!(1.0, 1.0, 1.0), color!(0.5, 0.7, 1.0))
+    }
+}
+
+/// Filter the image at `(u, v)`, wrapping around horizontally.
+fn sample_bilinear(image: &HdrImage, u: f64, v: f64) -> Color {
+    let (width, height) = (image.width(), image.height());
+    let x = u * width as f64 - 0.5;
+    let y = (v * height as f64 - 0.5).clamp(0.0, (height - 1) as f64);
+    let (x0, y0) = (x.floor(), y.floor());
+    let (tx, ty) = (x - x0, y - y0);
+
+    let column = |x: f64| (x as i64).rem_euclid(width as i64) as usize;
+    let (x0, x1) = (column(x0), column(x0 + 1.0));
+    let (y0, y1) = (y0 as usize, (y0 as usize + 1).min(height - 1));
+
+    let top = (1.0 - tx) * image.pixel(x0, y0) + tx * image.pixel(x1, y0);
+    let bottom = (1.0 - tx) * image.pixel(x0, y1) + tx * image.pixel(x1, y1);
+    (1.0 - ty) * top + ty * bottom
+}
@@ -0,0 +1,159 @@
+use crate::hdr;
+use crate::image::Image;
+use crate::tonemap::OutputSettings;
+use crate::vec3::Color;
+use exr::prelude::{self as openexr, f16, WritableImage};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// Type of the samples stored in OpenEXR files.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ExrPrecision {
+    /// 16 bits floats, enough for display and half the size.
+    Half,
+    #[default]
+    Float,
+}
+
+/// Linear RGB radiance as computed by the renderer, stored row by row starting
+/// from the top left corner.
+#[derive(Clone)]
+pub struct Film {
+    width: usize,
+    height: usize,
+    data: Vec<[f32; 3]>,
+}
+
+impl Film {
+    pub fn new(width: usize, height: usize) -> Self {
+        Film {
+            width,
+            height,
+            data: vec![[0.0; 3]; width * height],
+        }
+    }
+
+    /// Wrap pixels stored row by row.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `data` does not hold exactly `width * height` pixels.
+    pub fn from_raw(width: usize, height: usize, data: Vec<[f32; 3]>) -> Self {
+        assert_eq!(data.len(), width * height, "film size mismatch");
+        Film {
+            width,
+            height,
+            data,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> Color {
+        let [r, g, b] = self.data[y * self.width + x];
+        color!(r as f64, g as f64, b as f64)
+    }
+
+    pub fn set_pixel(&mut self, x: usize, y: usize, value: Color) {
+        self.data[y * self.width + x] = [value.x() as f32, value.y() as f32, value.z() as f32];
+    }
+
+    pub fn pixels(&self) -> &[[f32; 3]] {
+        &self.data
+    }
+
+    /// Turn the radiance into a displayable 8 bits sRGB image.
+    pub fn develop(&self, output: &OutputSettings) -> Image {
+        let data = self
+            .data
+            .iter()
+            .flat_map(|&[r, g, b]| output.encode(color!(r as f64, g as f64, b as f64)))
+            .collect();
+        Image::from_raw(self.width, self.height, data)
+    }
+
+    /// Write the film as a little endian Portable Float Map.
+    pub fn write_pfm<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        write!(writer, "PF\n{} {}\n-1.0\n", self.width, self.height)?;
+        // Rows are stored from the bottom up.
+        for row in self.data.chunks(self.width).rev() {
+            for channel in row.iter().flatten() {
+                writer.write_all(&channel.to_le_bytes())?;
+            }
+        }
+        writer.flush()
+    }
+
+    pub fn save_pfm<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.write_pfm(BufWriter::new(File::create(path)?))
+    }
+
+    /// Write the film as a Radiance HDR image.
+    pub fn write_hdr<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        hdr::write_rgbe(&mut writer, self.width, self.height, &self.data)?;
+        writer.flush()
+    }
+
+    pub fn save_hdr<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.write_hdr(BufWriter::new(File::create(path)?))
+    }
+
+    pub fn save_exr<P: AsRef<Path>>(
+        &self,
+        path: P,
+        precision: ExrPrecision,
+    ) -> Result<(), openexr::Error> {
+        save_exr_layers(path, &[("", self)], precision)
+    }
+}
+
+/// Write films of the same size as the layers of a single OpenEXR file, the
+/// RGB channels of each one being prefixed by its name, like `albedo.R`, and
+/// left bare for the layer named "".
+///
+/// # Panics
+///
+/// Panics if `layers` is empty or if the films do not have the same size.
+pub fn save_exr_layers<P: AsRef<Path>>(
+    path: P,
+    layers: &[(&str, &Film)],
+    precision: ExrPrecision,
+) -> Result<(), openexr::Error> {
+    let (_, first) = layers.first().expect("no layer to write");
+    let size = (first.width, first.height);
+
+    let mut channels = Vec::new();
+    for &(name, film) in layers {
+        assert_eq!((film.width, film.height), size, "layer size mismatch");
+        for (index, channel_name) in ["R", "G", "B"].into_iter().enumerate() {
+            let values = film.data.iter().map(|pixel| pixel[index]);
+            let samples = match precision {
+                ExrPrecision::Half => {
+                    openexr::FlatSamples::F16(values.map(f16::from_f32).collect())
+                }
+                ExrPrecision::Float => openexr::FlatSamples::F32(values.collect()),
+            };
+            let channel_name = if name.is_empty() {
+                channel_name.to_string()
+            } else {
+                format!("{}.{}", name, channel_name)
+            };
+            channels.push(openexr::AnyChannel::new(channel_name.as_str(), samples));
+        }
+    }
+
+    let layer = openexr::Layer::new(
+        size,
+        openexr::LayerAttributes::default(),
+        openexr::Encoding::SMALL_LOSSLESS,
+        openexr::AnyChannels::sort(openexr::SmallVec::from_vec(channels)),
+    );
+    openexr::Image::from_layer(layer).write().to_file(path)
+}
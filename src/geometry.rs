@@ -1 +1,4 @@
+pub mod mesh;
+pub mod quad;
 pub mod sphere;
+pub mod triangle;
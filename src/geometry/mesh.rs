@@ -0,0 +1,192 @@
+use super::triangle;
+use crate::aabb::Aabb;
+use crate::aabb::Interval;
+use crate::bvh::LinearBvh;
+use crate::hit::Hit;
+use crate::hit::Hittable;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::{cross, unit_vector, Point3, Vec3};
+use std::sync::Arc;
+
+/// Indices of the attributes used by the three corners of a mesh triangle.
+#[derive(Copy, Clone)]
+pub struct Face {
+    pub positions: [usize; 3],
+    pub normals: Option<[usize; 3]>,
+    pub uvs: Option<[usize; 3]>,
+}
+
+impl Face {
+    pub fn new(positions: [usize; 3]) -> Self {
+        Face {
+            positions,
+            normals: None,
+            uvs: None,
+        }
+    }
+}
+
+struct MeshData<M: Material> {
+    positions: Vec<Point3>,
+    normals: Vec<Vec3>,
+    uvs: Vec<[f64; 2]>,
+    faces: Vec<Face>,
+    material: M,
+}
+
+/// A triangle mesh sharing its vertex attributes between faces.
+///
+/// Faces with normal indices are smooth shaded by interpolating the vertex
+/// normals, faces with texture coordinate indices interpolate them into the
+/// `u`/`v` of the hit, otherwise the barycentric coordinates are used.
+pub struct TriangleMesh<M: Material> {
+    data: Arc<MeshData<M>>,
+    bvh: Option<LinearBvh>,
+}
+
+/// A single face of a mesh, stored in the internal BVH of the mesh.
+struct MeshTriangle<M: Material> {
+    mesh: Arc<MeshData<M>>,
+    index: usize,
+    bbox: Aabb,
+}
+
+impl<M: Material + 'static> TriangleMesh<M> {
+    /// Create a mesh from its attribute buffers.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a face refers to an attribute outside of its buffer.
+    pub fn new(
+        positions: Vec<Point3>,
+        normals: Vec<Vec3>,
+        uvs: Vec<[f64; 2]>,
+        faces: Vec<Face>,
+        material: M,
+    ) -> Self {
+        for face in &faces {
+            assert!(face.positions.iter().all(|&i| i < positions.len()));
+            if let Some(indices) = face.normals {
+                assert!(indices.iter().all(|&i| i < normals.len()));
+            }
+            if let Some(indices) = face.uvs {
+                assert!(indices.iter().all(|&i| i < uvs.len()));
+            }
+        }
+
+        let data = Arc::new(MeshData {
+            positions,
+            normals,
+            uvs,
+            faces,
+            material,
+        });
+
+        let triangles: Vec<Arc<dyn Hittable>> = (0..data.faces.len())
+            .map(|index| {
+                let bbox = triangle::bounding_box(&data.vertices(index));
+                Arc::new(MeshTriangle {
+                    mesh: data.clone(),
+                    index,
+                    bbox,
+                }) as Arc<dyn Hittable>
+            })
+            .collect();
+        let bvh = if triangles.is_empty() {
+            None
+        } else {
+            Some(LinearBvh::from_slice(&triangles))
+        };
+
+        TriangleMesh { data, bvh }
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.data.faces.len()
+    }
+}
+
+impl<M: Material> MeshData<M> {
+    fn vertices(&self, index: usize) -> [Point3; 3] {
+        self.faces[index].positions.map(|i| self.positions[i])
+    }
+}
+
+impl<M: Material> Hittable for TriangleMesh<M> {
+    fn bounding_box(&self) -> Aabb {
+        self.bvh
+            .as_ref()
+            .map(|bvh| bvh.bounding_box())
+            .unwrap_or_default()
+    }
+
+    fn hit(&self, ray: Ray, ray_t: Interval) -> Option<Hit<'_>> {
+        self.bvh.as_ref()?.hit(ray, ray_t)
+    }
+}
+
+impl<M: Material> Hittable for MeshTriangle<M> {
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    fn hit(&self, ray: Ray, ray_t: Interval) -> Option<Hit<'_>> {
+        let mesh = &*self.mesh;
+        let face = &mesh.faces[self.index];
+        let vertices @ [a, b, c] = mesh.vertices(self.index);
+        let (t, b1, b2) = triangle::intersect(&vertices, ray, ray_t)?;
+        let b0 = 1.0 - b1 - b2;
+
+        let outward_normal = unit_vector(cross(b - a, c - a));
+        let mut hit = Hit::new(ray.at(t), outward_normal, t, ray, &mesh.material);
+
+        // Null or opposing vertex normals keep the geometric normal.
+        if let Some([n0, n1, n2]) = face.normals {
+            let shading_normal =
+                b0 * mesh.normals[n0] + b1 * mesh.normals[n1] + b2 * mesh.normals[n2];
+            if !shading_normal.near_zero() {
+                let shading_normal = unit_vector(shading_normal);
+                hit.normal = if hit.front_face {
+                    shading_normal
+                } else {
+                    -shading_normal
+                };
+            }
+        }
+
+        let ((u, v), (dpdu, dpdv)) = match face.uvs {
+            Some([t0, t1, t2]) => {
+                let [uv0, uv1, uv2] = [mesh.uvs[t0], mesh.uvs[t1], mesh.uvs[t2]];
+                (
+                    (
+                        b0 * uv0[0] + b1 * uv1[0] + b2 * uv2[0],
+                        b0 * uv0[1] + b1 * uv1[1] + b2 * uv2[1],
+                    ),
+                    uv_derivatives(vertices, [uv0, uv1, uv2]),
+                )
+            }
+            None => ((b1, b2), (b - a, c - a)),
+        };
+
+        Some(hit.with_uv(u, v).with_derivatives(dpdu, dpdv))
+    }
+}
+
+/// Partial derivatives of the position with respect to the texture
+/// coordinates over a triangle, null when the coordinates are degenerate.
+fn uv_derivatives([p0, p1, p2]: [Point3; 3], [uv0, uv1, uv2]: [[f64; 2]; 3]) -> (Vec3, Vec3) {
+    let (duv02, duv12) = (
+        [uv0[0] - uv2[0], uv0[1] - uv2[1]],
+        [uv1[0] - uv2[0], uv1[1] - uv2[1]],
+    );
+    let (dp02, dp12) = (p0 - p2, p1 - p2);
+    let determinant = duv02[0] * duv12[1] - duv02[1] * duv12[0];
+    if determinant.abs() < 1e-12 {
+        return (Vec3::default(), Vec3::default());
+    }
+    (
+        (duv12[1] * dp02 - duv02[1] * dp12) / determinant,
+        (duv02[0] * dp12 - duv12[0] * dp02) / determinant,
+    )
+}
@@ -0,0 +1,105 @@
+use crate::aabb::Aabb;
+use crate::aabb::Interval;
+use crate::hit::Hit;
+use crate::hit::Hittable;
+use crate::hittable_list::HittableList;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::{cross, dot, unit_vector, Point3, Vec3};
+use std::sync::Arc;
+
+/// A parallelogram defined by a corner and the two edges starting from it.
+pub struct Quad<M: Material> {
+    q: Point3,
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+    normal: Vec3,
+    d: f64,
+    material: M,
+    bbox: Aabb,
+}
+
+impl<M: Material> Quad<M> {
+    pub fn new(q: Point3, u: Vec3, v: Vec3, material: M) -> Self {
+        let n = cross(u, v);
+        let normal = unit_vector(n);
+        let bbox = Aabb::surrounding(
+            &Aabb::from_extremum(q, q + u + v),
+            &Aabb::from_extremum(q + u, q + v),
+        );
+
+        Quad {
+            q,
+            u,
+            v,
+            w: n / dot(n, n),
+            normal,
+            d: dot(normal, q),
+            material,
+            bbox: bbox.pad(0.0001),
+        }
+    }
+}
+
+impl<M: Material> Hittable for Quad<M> {
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    fn hit(&self, ray: Ray, ray_t: Interval) -> Option<Hit<'_>> {
+        let denom = dot(self.normal, ray.direction());
+        // The ray is parallel to the plane.
+        if denom.abs() < 1e-8 {
+            return None;
+        }
+
+        let t = (self.d - dot(self.normal, ray.origin())) / denom;
+        if !ray_t.contains(t) {
+            return None;
+        }
+
+        // Express the intersection in the (u, v) basis of the plane.
+        let intersection = ray.at(t);
+        let planar = intersection - self.q;
+        let alpha = dot(self.w, cross(planar, self.v));
+        let beta = dot(self.w, cross(self.u, planar));
+        let unit = Interval::new(0.0, 1.0);
+        if !unit.contains(alpha) || !unit.contains(beta) {
+            return None;
+        }
+
+        Some(
+            Hit::new(intersection, self.normal, t, ray, &self.material)
+                .with_uv(alpha, beta)
+                .with_derivatives(self.u, self.v),
+        )
+    }
+}
+
+/// Build the axis-aligned box with opposite corners `a` and `b` out of six
+/// quads sharing the same material.
+pub fn cuboid<M: Material + Clone + 'static>(a: Point3, b: Point3, material: M) -> HittableList {
+    let mut sides = HittableList::new();
+
+    let min = point!(a.x().min(b.x()), a.y().min(b.y()), a.z().min(b.z()));
+    let max = point!(a.x().max(b.x()), a.y().max(b.y()), a.z().max(b.z()));
+
+    let dx = vec3!(max.x() - min.x(), 0.0, 0.0);
+    let dy = vec3!(0.0, max.y() - min.y(), 0.0);
+    let dz = vec3!(0.0, 0.0, max.z() - min.z());
+
+    let faces = [
+        (point!(min.x(), min.y(), max.z()), dx, dy),  // front
+        (point!(max.x(), min.y(), max.z()), -dz, dy), // right
+        (point!(max.x(), min.y(), min.z()), -dx, dy), // back
+        (point!(min.x(), min.y(), min.z()), dz, dy),  // left
+        (point!(min.x(), max.y(), max.z()), dx, -dz), // top
+        (point!(min.x(), min.y(), min.z()), dx, dz),  // bottom
+    ];
+    for (q, u, v) in faces {
+        sides.add(Arc::new(Quad::new(q, u, v, material.clone())));
+    }
+
+    sides
+}
@@ -4,8 +4,8 @@ use crate::hit::Hit;
 use crate::hit::Hittable;
 use crate::material::Material;
 use crate::ray::Ray;
-use crate::vec3;
 use crate::vec3::{dot, Point3, Vec3};
+use std::f64::consts::PI;
 
 pub struct Sphere<M: Material> {
     center: Point3,
@@ -53,7 +53,7 @@ impl<M: Material> Hittable for Sphere<M> {
         self.bbox
     }
 
-    fn hit(&self, ray: Ray, ray_t: Interval) -> Option<Hit> {
+    fn hit(&self, ray: Ray, ray_t: Interval) -> Option<Hit<'_>> {
         let center = if self.is_moving {
             self.center(ray.time())
         } else {
@@ -78,13 +78,36 @@ impl<M: Material> Hittable for Sphere<M> {
             }
         }
 
-        let outward_normal = (ray.at(root) - self.center) / self.radius;
-        Some(Hit::new(
-            ray.at(root),
-            outward_normal,
-            root,
-            ray,
-            &self.material,
-        ))
+        let outward_normal = (ray.at(root) - center) / self.radius;
+        let (u, v) = sphere_uv(outward_normal);
+        let (dpdu, dpdv) = sphere_derivatives(outward_normal, self.radius);
+        Some(
+            Hit::new(ray.at(root), outward_normal, root, ray, &self.material)
+                .with_uv(u, v)
+                .with_derivatives(dpdu, dpdv),
+        )
     }
 }
+
+/// Partial derivatives of the position on a sphere of `radius` with respect
+/// to the coordinates of `sphere_uv`, at the point in the unit `direction`.
+fn sphere_derivatives(direction: Vec3, radius: f64) -> (Vec3, Vec3) {
+    let (x, y, z) = (direction.x(), direction.y(), direction.z());
+    let dpdu = 2.0 * PI * radius * vec3!(z, 0.0, -x);
+    let sin_theta = (1.0 - y * y).max(0.0).sqrt();
+    if sin_theta < 1e-9 {
+        // The longitude is undefined at the poles.
+        return (dpdu, Vec3::default());
+    }
+    let dpdv = PI * radius * vec3!(-x * y / sin_theta, sin_theta, -y * z / sin_theta);
+    (dpdu, dpdv)
+}
+
+/// Surface coordinates of the point of the unit sphere in `direction`: `u`
+/// is the angle around the Y axis starting from -X, `v` the angle from -Y
+/// up to +Y, both brought back to [0, 1].
+pub fn sphere_uv(direction: Vec3) -> (f64, f64) {
+    let theta = (-direction.y()).clamp(-1.0, 1.0).acos();
+    let phi = f64::atan2(-direction.z(), direction.x()) + PI;
+    (phi / (2.0 * PI), theta / PI)
+}
@@ -0,0 +1,88 @@
+use crate::aabb::Aabb;
+use crate::aabb::Interval;
+use crate::hit::Hit;
+use crate::hit::Hittable;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::{cross, dot, unit_vector, Point3};
+
+pub struct Triangle<M: Material> {
+    vertices: [Point3; 3],
+    material: M,
+    bbox: Aabb,
+}
+
+impl<M: Material> Triangle<M> {
+    pub fn new(a: Point3, b: Point3, c: Point3, material: M) -> Self {
+        Triangle {
+            vertices: [a, b, c],
+            material,
+            bbox: bounding_box(&[a, b, c]),
+        }
+    }
+}
+
+impl<M: Material> Hittable for Triangle<M> {
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    fn hit(&self, ray: Ray, ray_t: Interval) -> Option<Hit<'_>> {
+        let [a, b, c] = self.vertices;
+        let (t, u, v) = intersect(&self.vertices, ray, ray_t)?;
+        let outward_normal = unit_vector(cross(b - a, c - a));
+        Some(
+            Hit::new(ray.at(t), outward_normal, t, ray, &self.material)
+                .with_uv(u, v)
+                .with_derivatives(b - a, c - a),
+        )
+    }
+}
+
+/// Box around a triangle, padded since it is flat along at least one axis
+/// when it is axis-aligned.
+pub(crate) fn bounding_box(vertices: &[Point3; 3]) -> Aabb {
+    let [a, b, c] = vertices;
+    Aabb::surrounding(&Aabb::from_extremum(*a, *b), &Aabb::from_extremum(*b, *c)).pad(0.0001)
+}
+
+/// Möller–Trumbore ray/triangle intersection.
+///
+/// Returns the ray parameter of the hit along with the barycentric coordinates
+/// of the hit point relative to the second and third vertices.
+pub(crate) fn intersect(
+    vertices: &[Point3; 3],
+    ray: Ray,
+    ray_t: Interval,
+) -> Option<(f64, f64, f64)> {
+    let [a, b, c] = *vertices;
+    let edge1 = b - a;
+    let edge2 = c - a;
+
+    let pvec = cross(ray.direction(), edge2);
+    let det = dot(edge1, pvec);
+    // The ray is parallel to the triangle.
+    if det.abs() < 1e-12 {
+        return None;
+    }
+    let inv_det = 1.0 / det;
+
+    let tvec = ray.origin() - a;
+    let u = dot(tvec, pvec) * inv_det;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+
+    let qvec = cross(tvec, edge1);
+    let v = dot(ray.direction(), qvec) * inv_det;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+
+    let t = dot(edge2, qvec) * inv_det;
+    if !ray_t.contains(t) {
+        return None;
+    }
+
+    Some((t, u, v))
+}
@@ -0,0 +1,302 @@
+//! Radiance RGBE (`.hdr`) images.
+
+use crate::vec3::Color;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::Path;
+
+#[derive(Debug)]
+pub enum HdrError {
+    Io(io::Error),
+    Format(String),
+}
+
+impl fmt::Display for HdrError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HdrError::Io(err) => write!(f, "{}", err),
+            HdrError::Format(message) => write!(f, "invalid Radiance HDR file: {}", message),
+        }
+    }
+}
+
+impl Error for HdrError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            HdrError::Io(err) => Some(err),
+            HdrError::Format(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for HdrError {
+    fn from(err: io::Error) -> Self {
+        HdrError::Io(err)
+    }
+}
+
+/// Largest width or height accepted when reading an image.
+const MAX_DIMENSION: usize = 1 << 16;
+
+/// Largest number of pixels accepted when reading an image, a 16k by 8k
+/// environment map.
+const MAX_PIXELS: usize = 1 << 27;
+
+/// A linear floating point RGB image, stored row by row starting from the top
+/// left corner.
+#[derive(Clone)]
+pub struct HdrImage {
+    width: usize,
+    height: usize,
+    data: Vec<Color>,
+}
+
+impl HdrImage {
+    pub fn new(width: usize, height: usize) -> Self {
+        HdrImage {
+            width,
+            height,
+            data: vec![Color::default(); width * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> Color {
+        self.data[y * self.width + x]
+    }
+
+    pub fn set_pixel(&mut self, x: usize, y: usize, value: Color) {
+        self.data[y * self.width + x] = value;
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, HdrError> {
+        Self::read(BufReader::new(File::open(path)?))
+    }
+
+    pub fn read<R: BufRead>(mut reader: R) -> Result<Self, HdrError> {
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        if !line.starts_with("#?") {
+            return Err(HdrError::Format(String::from("missing `#?` signature")));
+        }
+
+        // Header lines until an empty one, only the format matters to us.
+        loop {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 {
+                return Err(HdrError::Format(String::from("unterminated header")));
+            }
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some(format) = line.strip_prefix("FORMAT=") {
+                if format != "32-bit_rle_rgbe" {
+                    return Err(HdrError::Format(format!("unsupported format `{}`", format)));
+                }
+            }
+        }
+
+        line.clear();
+        reader.read_line(&mut line)?;
+        let (width, height) = match line.split_whitespace().collect::<Vec<_>>()[..] {
+            ["-Y", height, "+X", width] => (width.parse().ok(), height.parse().ok()),
+            _ => (None, None),
+        };
+        let (width, height): (usize, usize) = match (width, height) {
+            (Some(width), Some(height)) => (width, height),
+            _ => {
+                return Err(HdrError::Format(format!(
+                    "unsupported resolution line `{}`",
+                    line.trim_end()
+                )))
+            }
+        };
+        if width == 0 || height == 0 {
+            return Err(HdrError::Format(format!(
+                "empty {}x{} image",
+                width, height
+            )));
+        }
+        if width > MAX_DIMENSION || height > MAX_DIMENSION || width * height > MAX_PIXELS {
+            return Err(HdrError::Format(format!(
+                "{}x{} image is too large",
+                width, height
+            )));
+        }
+
+        let mut image = HdrImage::new(width, height);
+        let mut scanline = vec![[0u8; 4]; width];
+        for y in 0..height {
+            read_scanline(&mut reader, &mut scanline)?;
+            for (x, rgbe) in scanline.iter().enumerate() {
+                image.set_pixel(x, y, from_rgbe(*rgbe));
+            }
+        }
+
+        Ok(image)
+    }
+}
+
+fn read_byte<R: Read>(reader: &mut R) -> Result<u8, HdrError> {
+    let mut byte = [0u8; 1];
+    reader.read_exact(&mut byte)?;
+    Ok(byte[0])
+}
+
+/// Read a scanline, either flat or using the adaptive run length encoding.
+fn read_scanline<R: Read>(reader: &mut R, scanline: &mut [[u8; 4]]) -> Result<(), HdrError> {
+    let width = scanline.len();
+    let mut first = [0u8; 4];
+    reader.read_exact(&mut first)?;
+
+    let encoded = (8..0x8000).contains(&width) && first[0] == 2 && first[1] == 2;
+    if !encoded || first[2] & 0x80 != 0 {
+        scanline[0] = first;
+        for pixel in scanline.iter_mut().skip(1) {
+            reader.read_exact(pixel)?;
+        }
+        return Ok(());
+    }
+
+    if ((first[2] as usize) << 8 | first[3] as usize) != width {
+        return Err(HdrError::Format(String::from("scanline width mismatch")));
+    }
+
+    // Each of the four components is encoded separately.
+    for component in 0..4 {
+        let mut x = 0;
+        while x < width {
+            let count = read_byte(reader)? as usize;
+            let (run, count) = if count > 128 {
+                (true, count - 128)
+            } else {
+                (false, count)
+            };
+            if count == 0 || x + count > width {
+                return Err(HdrError::Format(String::from("bad scanline data")));
+            }
+
+            if run {
+                let value = read_byte(reader)?;
+                for pixel in &mut scanline[x..x + count] {
+                    pixel[component] = value;
+                }
+            } else {
+                for pixel in &mut scanline[x..x + count] {
+                    pixel[component] = read_byte(reader)?;
+                }
+            }
+            x += count;
+        }
+    }
+
+    Ok(())
+}
+
+fn from_rgbe([r, g, b, e]: [u8; 4]) -> Color {
+    if e == 0 {
+        return Color::default();
+    }
+    let f = 2f64.powi(e as i32 - (128 + 8));
+    color!(r as f64 * f, g as f64 * f, b as f64 * f)
+}
+
+/// Write linear RGB pixels, stored row by row from the top left corner, as a
+/// run length encoded Radiance HDR image.
+pub fn write_rgbe<W: Write>(
+    mut writer: W,
+    width: usize,
+    height: usize,
+    pixels: &[[f32; 3]],
+) -> io::Result<()> {
+    assert_eq!(pixels.len(), width * height, "image size mismatch");
+    write!(
+        writer,
+        "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+        height, width
+    )?;
+
+    let encoded = (8..0x8000).contains(&width);
+    let mut components = vec![0u8; width];
+    for row in pixels.chunks(width) {
+        let scanline: Vec<[u8; 4]> = row.iter().map(|&pixel| to_rgbe(pixel)).collect();
+        if !encoded {
+            for pixel in &scanline {
+                writer.write_all(pixel)?;
+            }
+            continue;
+        }
+
+        writer.write_all(&[2, 2, (width >> 8) as u8, width as u8])?;
+        for component in 0..4 {
+            for (value, pixel) in components.iter_mut().zip(&scanline) {
+                *value = pixel[component];
+            }
+            write_runs(&mut writer, &components)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Encode one component of a scanline as runs of equal bytes and literal
+/// chunks.
+fn write_runs<W: Write>(writer: &mut W, data: &[u8]) -> io::Result<()> {
+    const MIN_RUN: usize = 4;
+    let run_length = |start: usize, limit: usize| {
+        data[start..]
+            .iter()
+            .take(limit)
+            .take_while(|&&byte| byte == data[start])
+            .count()
+    };
+
+    let mut i = 0;
+    while i < data.len() {
+        let run = run_length(i, 127);
+        if run >= MIN_RUN {
+            writer.write_all(&[128 + run as u8, data[i]])?;
+            i += run;
+            continue;
+        }
+
+        let start = i;
+        while i < data.len() && i - start < 128 && run_length(i, MIN_RUN) < MIN_RUN {
+            i += 1;
+        }
+        writer.write_all(&[(i - start) as u8])?;
+        writer.write_all(&data[start..i])?;
+    }
+
+    Ok(())
+}
+
+fn to_rgbe([r, g, b]: [f32; 3]) -> [u8; 4] {
+    let (r, g, b) = (r.max(0.0), g.max(0.0), b.max(0.0));
+    let max = r.max(g).max(b);
+    if max < 1e-32 || !max.is_finite() {
+        return [0; 4];
+    }
+    // Shared exponent such that `max / 2^exponent` is in `[0.5, 1)`.
+    let mut exponent = max.log2().floor() as i32 + 1;
+    if max / 2f32.powi(exponent) >= 1.0 {
+        exponent += 1;
+    }
+    let scale = 256.0 / 2f32.powi(exponent);
+    [
+        (r * scale) as u8,
+        (g * scale) as u8,
+        (b * scale) as u8,
+        (exponent + 128).clamp(0, 255) as u8,
+    ]
+}
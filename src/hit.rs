@@ -3,6 +3,7 @@ use crate::material::Material;
 use crate::ray::Ray;
 use crate::vec3::*;
 
+#[derive(Copy, Clone)]
 pub struct Hit<'a> {
     pub p: Point3,
     pub normal: Vec3,
@@ -10,9 +11,28 @@ pub struct Hit<'a> {
     pub t: f64,
     pub u: f64,
     pub v: f64,
+    /// Partial derivatives of the position with respect to `u` and `v`, null
+    /// when unknown.
+    pub dpdu: Vec3,
+    pub dpdv: Vec3,
+    /// Area of the surface seen by the pixel, for the textures to filter.
+    pub footprint: Footprint,
     pub front_face: bool,
 }
 
+/// Variation of the position and the surface coordinates of a hit point from
+/// one pixel to the next, along the horizontal (`x`) and vertical (`y`) axes
+/// of the image. Null for a point, when the ray had no differentials.
+#[derive(Copy, Clone, Default)]
+pub struct Footprint {
+    pub dpdx: Vec3,
+    pub dpdy: Vec3,
+    pub dudx: f64,
+    pub dvdx: f64,
+    pub dudy: f64,
+    pub dvdy: f64,
+}
+
 impl<'a> Hit<'a> {
     pub fn new(
         p: Point3,
@@ -28,6 +48,9 @@ impl<'a> Hit<'a> {
             t,
             u: 0.0,
             v: 0.0,
+            dpdu: Vec3::default(),
+            dpdv: Vec3::default(),
+            footprint: Footprint::default(),
             front_face,
             normal: if front_face {
                 outward_normal
@@ -36,9 +59,77 @@ impl<'a> Hit<'a> {
             },
         }
     }
+
+    /// Set the surface coordinates of the hit point.
+    pub fn with_uv(mut self, u: f64, v: f64) -> Self {
+        self.u = u;
+        self.v = v;
+        self
+    }
+
+    /// Set the partial derivatives of the position with respect to the
+    /// surface coordinates.
+    pub fn with_derivatives(mut self, dpdu: Vec3, dpdv: Vec3) -> Self {
+        self.dpdu = dpdu;
+        self.dpdv = dpdv;
+        self
+    }
+
+    /// Estimate the footprint from the differentials of `ray`, the ray that
+    /// found the hit, by intersecting them with the tangent plane.
+    pub fn with_footprint(mut self, ray: &Ray) -> Self {
+        let Some(differentials) = ray.differentials() else {
+            return self;
+        };
+        let distance = dot(self.normal, self.p);
+        let offset = |origin: Point3, direction: Vec3| {
+            let cos = dot(self.normal, direction);
+            // Differential parallel to the surface at a grazing hit.
+            if cos.abs() < 1e-12 {
+                return None;
+            }
+            let t = (distance - dot(self.normal, origin)) / cos;
+            Some(origin + t * direction - self.p)
+        };
+        let (Some(dpdx), Some(dpdy)) = (
+            offset(differentials.x_origin, differentials.x_direction),
+            offset(differentials.y_origin, differentials.y_direction),
+        ) else {
+            return self;
+        };
+
+        // Least squares solution of dp = dpdu du + dpdv dv.
+        let (a00, a01, a11) = (
+            dot(self.dpdu, self.dpdu),
+            dot(self.dpdu, self.dpdv),
+            dot(self.dpdv, self.dpdv),
+        );
+        let determinant = a00 * a11 - a01 * a01;
+        let solve = |dp: Vec3| {
+            if determinant.abs() < 1e-12 {
+                return (0.0, 0.0);
+            }
+            let (b0, b1) = (dot(self.dpdu, dp), dot(self.dpdv, dp));
+            (
+                (a11 * b0 - a01 * b1) / determinant,
+                (a00 * b1 - a01 * b0) / determinant,
+            )
+        };
+        let (dudx, dvdx) = solve(dpdx);
+        let (dudy, dvdy) = solve(dpdy);
+        self.footprint = Footprint {
+            dpdx,
+            dpdy,
+            dudx,
+            dvdx,
+            dudy,
+            dvdy,
+        };
+        self
+    }
 }
 
 pub trait Hittable: Sync + Send {
-    fn hit(&self, ray: Ray, ray_t: Interval) -> Option<Hit>;
+    fn hit(&self, ray: Ray, ray_t: Interval) -> Option<Hit<'_>>;
     fn bounding_box(&self) -> Aabb;
 }
@@ -0,0 +1,48 @@
+use crate::aabb::{Aabb, Interval};
+use crate::hit::{Hit, Hittable};
+use crate::ray::Ray;
+use std::sync::Arc;
+
+#[derive(Clone, Default)]
+pub struct HittableList {
+    objects: Vec<Arc<dyn Hittable>>,
+    bbox: Aabb,
+}
+
+impl HittableList {
+    pub fn new() -> Self {
+        HittableList {
+            objects: vec![],
+            bbox: Aabb::default(),
+        }
+    }
+
+    pub fn add(&mut self, object: Arc<dyn Hittable>) {
+        self.bbox = Aabb::surrounding(&self.bbox, &object.bounding_box());
+        self.objects.push(object);
+    }
+
+    pub fn objects(&self) -> &[Arc<dyn Hittable>] {
+        &self.objects
+    }
+}
+
+impl Hittable for HittableList {
+    fn hit(&self, ray: Ray, ray_t: Interval) -> Option<Hit<'_>> {
+        let mut closest_so_far = ray_t.maximum;
+        let mut result = None;
+
+        for object in &self.objects {
+            if let Some(hit) = object.hit(ray, Interval::new(ray_t.minimum, closest_so_far)) {
+                closest_so_far = hit.t;
+                result = Some(hit);
+            }
+        }
+
+        result
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
@@ -0,0 +1,79 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+pub mod writer;
+
+pub use writer::{ImageFormat, ImageWriter};
+
+/// An 8 bits per channel RGB image, stored row by row starting from the top
+/// left corner.
+pub struct Image {
+    width: usize,
+    height: usize,
+    data: Vec<u8>,
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize) -> Self {
+        Image {
+            width,
+            height,
+            data: vec![0; width * height * 3],
+        }
+    }
+
+    /// Wrap raw interleaved RGB bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `data` does not hold exactly `width * height` pixels.
+    pub fn from_raw(width: usize, height: usize, data: Vec<u8>) -> Self {
+        assert_eq!(data.len(), width * height * 3, "image size mismatch");
+        Image {
+            width,
+            height,
+            data,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> [u8; 3] {
+        let index = (y * self.width + x) * 3;
+        [self.data[index], self.data[index + 1], self.data[index + 2]]
+    }
+
+    pub fn set_pixel(&mut self, x: usize, y: usize, value: [u8; 3]) {
+        let index = (y * self.width + x) * 3;
+        self.data[index..index + 3].copy_from_slice(&value);
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.data
+    }
+
+    pub fn write_png<W: Write>(&self, writer: W) -> Result<(), png::EncodingError> {
+        let mut encoder = png::Encoder::new(writer, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.data)
+    }
+
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> Result<(), png::EncodingError> {
+        let file = File::create(path)?;
+        self.write_png(BufWriter::new(file))
+    }
+}
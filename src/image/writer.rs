@@ -0,0 +1,129 @@
+//! Encoders of 8 bits images into the file formats the renderer writes.
+
+use super::Image;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+pub trait ImageWriter {
+    fn write(&self, image: &Image, writer: &mut dyn Write) -> io::Result<()>;
+
+    fn save(&self, image: &Image, path: &Path) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(image, &mut writer)?;
+        writer.flush()
+    }
+}
+
+pub struct PngWriter;
+
+impl ImageWriter for PngWriter {
+    fn write(&self, image: &Image, writer: &mut dyn Write) -> io::Result<()> {
+        Ok(image.write_png(writer)?)
+    }
+}
+
+/// Plain text Netpbm image, one pixel by line so that two images can be
+/// compared with a text diff.
+pub struct AsciiPpmWriter;
+
+impl ImageWriter for AsciiPpmWriter {
+    fn write(&self, image: &Image, writer: &mut dyn Write) -> io::Result<()> {
+        writeln!(writer, "P3\n{} {}\n255", image.width(), image.height())?;
+        for pixel in image.as_bytes().chunks_exact(3) {
+            writeln!(writer, "{}", colorize([pixel[0], pixel[1], pixel[2]]))?;
+        }
+        Ok(())
+    }
+}
+
+/// Raw Netpbm image, the samples following the header as bytes.
+pub struct BinaryPpmWriter;
+
+impl ImageWriter for BinaryPpmWriter {
+    fn write(&self, image: &Image, writer: &mut dyn Write) -> io::Result<()> {
+        write!(writer, "P6\n{} {}\n255\n", image.width(), image.height())?;
+        writer.write_all(image.as_bytes())
+    }
+}
+
+fn colorize([r, g, b]: [u8; 3]) -> String {
+    format!("{} {} {}", r, g, b)
+}
+
+/// The 8 bits image formats selectable from the command line.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ImageFormat {
+    #[default]
+    Png,
+    /// ASCII PPM, `P3`.
+    PpmAscii,
+    /// Binary PPM, `P6`.
+    Ppm,
+}
+
+impl ImageFormat {
+    pub const ALL: [ImageFormat; 3] = [ImageFormat::Png, ImageFormat::PpmAscii, ImageFormat::Ppm];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::PpmAscii => "ppm-ascii",
+            ImageFormat::Ppm => "ppm",
+        }
+    }
+
+    /// Format of the files with the given extension, binary for PPM.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "png" => Some(ImageFormat::Png),
+            "ppm" => Some(ImageFormat::Ppm),
+            _ => None,
+        }
+    }
+
+    pub fn writer(&self) -> Box<dyn ImageWriter> {
+        match self {
+            ImageFormat::Png => Box::new(PngWriter),
+            ImageFormat::PpmAscii => Box::new(AsciiPpmWriter),
+            ImageFormat::Ppm => Box::new(BinaryPpmWriter),
+        }
+    }
+}
+
+impl fmt::Display for ImageFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Debug)]
+pub struct UnknownImageFormatError(String);
+
+impl fmt::Display for UnknownImageFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names: Vec<_> = ImageFormat::ALL.iter().map(ImageFormat::name).collect();
+        write!(
+            f,
+            "unknown image format `{}`, expected one of {}",
+            self.0,
+            names.join(", ")
+        )
+    }
+}
+
+impl Error for UnknownImageFormatError {}
+
+impl FromStr for ImageFormat {
+    type Err = UnknownImageFormatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ImageFormat::ALL
+            .into_iter()
+            .find(|format| format.name() == s)
+            .ok_or_else(|| UnknownImageFormatError(s.to_string()))
+    }
+}
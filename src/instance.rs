@@ -0,0 +1,49 @@
+use crate::aabb::{Aabb, Interval};
+use crate::hit::{Hit, Hittable};
+use crate::ray::Ray;
+use crate::transform::Transform;
+use std::sync::Arc;
+
+/// An object placed in the world through a transform, the geometry being
+/// shared between all the instances of the same object.
+pub struct Instance {
+    object: Arc<dyn Hittable>,
+    transform: Transform,
+    bbox: Aabb,
+}
+
+impl Instance {
+    pub fn new(object: Arc<dyn Hittable>, transform: Transform) -> Self {
+        let bbox = transform.bounding_box(&object.bounding_box());
+        Instance {
+            object,
+            transform,
+            bbox,
+        }
+    }
+
+    pub fn transform(&self) -> &Transform {
+        &self.transform
+    }
+}
+
+impl Hittable for Instance {
+    fn hit(&self, ray: Ray, ray_t: Interval) -> Option<Hit<'_>> {
+        // The direction is not normalized so that `t` is the same in both
+        // spaces.
+        let inverse = self.transform.inverse();
+        let object_ray = Ray::new(inverse.point(ray.origin()), inverse.vector(ray.direction()))
+            .timed(ray.time());
+
+        let mut hit = self.object.hit(object_ray, ray_t)?;
+        hit.p = self.transform.point(hit.p);
+        hit.normal = self.transform.normal(hit.normal);
+        hit.dpdu = self.transform.vector(hit.dpdu);
+        hit.dpdv = self.transform.vector(hit.dpdv);
+        Some(hit)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
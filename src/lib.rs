@@ -0,0 +1,53 @@
+#[macro_use]
+pub mod vec3;
+pub mod aabb;
+pub mod aov;
+pub mod bvh;
+pub mod camera;
+pub mod denoise;
+pub mod environment;
+pub mod film;
+pub mod geometry;
+pub mod hdr;
+pub mod hit;
+pub mod hittable_list;
+pub mod image;
+pub mod instance;
+pub mod material;
+pub mod obj;
+pub mod ray;
+pub mod render;
+pub mod sampler;
+pub mod scene;
+pub mod texture;
+pub mod tonemap;
+pub mod transform;
+pub mod utils;
+
+pub use aabb::{Aabb, Interval};
+pub use aov::{Aov, AovFilms};
+pub use bvh::{Bvh, LinearBvh};
+pub use camera::Camera;
+pub use denoise::Denoiser;
+pub use environment::Environment;
+pub use film::Film;
+pub use hit::{Hit, Hittable};
+pub use hittable_list::HittableList;
+pub use image::Image;
+pub use instance::Instance;
+pub use material::Material;
+pub use ray::Ray;
+pub use render::adaptive::AdaptiveSampling;
+pub use render::cancel::CancelToken;
+pub use render::progress::ProgressSink;
+pub use render::progressive::{Accumulation, Progressive};
+pub use render::tile::TileOrder;
+pub use render::{
+    render, render_cancellable, render_progressive, render_with_aovs, PartialRender,
+    RenderSettings, SettingsError,
+};
+pub use sampler::{Sampler, SamplerKind};
+pub use texture::Texture;
+pub use tonemap::{OutputSettings, ToneMap};
+pub use transform::{Matrix4, Transform};
+pub use vec3::{Color, Point3, Vec3};
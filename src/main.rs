@@ -1,144 +1,223 @@
-use crate::vec3::*;
-use aabb::{Aabb, Interval};
-use bvh::Bvh;
-use camera::Camera;
-use hit::{Hit, Hittable};
-use ray::Ray;
-use render::{render, RenderSettings};
-use scene::*;
-use std::fs::File;
-use std::io::BufWriter;
+use clap::Parser;
+use cli::{Cli, Command, Progress, RenderArgs};
+use raytracer::film;
+use raytracer::image::ImageFormat;
+use raytracer::render::progress;
+use raytracer::scene::{file, SCENES};
+use raytracer::{
+    render_progressive, Accumulation, Aov, AovFilms, CancelToken, Film, HittableList, LinearBvh,
+    OutputSettings, ProgressSink, Progressive, RenderSettings,
+};
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 use std::sync::Arc;
 
-mod geometry;
-mod hit;
-mod ray;
-#[macro_use]
-mod vec3;
-mod aabb;
-mod bvh;
-mod camera;
-mod material;
-mod render;
-mod scene;
-mod texture;
-mod utils;
-
-fn main() {
-    let settings = RenderSettings::default(); //new(400, 16.0 / 9.0);
-
-    let selection = 2;
-    let pixels = match selection {
-        0 => {
-            let mut world = HittableList::new();
-            world.add(Arc::new(Bvh::new(final_first())));
-
-            // Camera
-            let look_from = point!(13.0, 2.0, 3.0);
-            let look_at = point!(0.0, 0.0, 0.0);
-
-            let cam = camera::Builder::new(look_from, look_at, 20.0, 0.1, 10.0)
-                .timed(0.0, 1.0)
-                .build();
-
-            render(&world, &cam, &settings)
+mod cli;
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let result = match cli.command {
+        Command::Render(args) => render_scene(&args),
+        Command::ListScenes => {
+            list_scenes();
+            Ok(())
         }
+    };
 
-        1 => {
-            let mut world = HittableList::new();
-            world.add(Arc::new(Bvh::new(random_scene())));
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
 
-            // Camera
-            let look_from = point!(13.0, 2.0, 3.0);
-            let look_at = point!(0.0, 0.0, 0.0);
+fn list_scenes() {
+    for scene in SCENES {
+        println!("{:<16}{}", scene.name, scene.description);
+    }
+}
 
-            let cam = camera::Builder::new(look_from, look_at, 20.0, 0.1, 10.0)
-                .timed(0.0, 1.0)
-                .build();
+fn render_scene(args: &RenderArgs) -> Result<(), Box<dyn Error>> {
+    let (mut settings, mut camera, mut environment, mut output, world, scene_id) =
+        match (&args.file, args.scene) {
+            (Some(path), _) => {
+                let scene = file::load(path)?;
+                let mut settings = scene.settings;
+                args.settings.apply(&mut settings)?;
+                let source = fs::read(path)
+                    .map_err(|err| format!("cannot read {}: {}", path.display(), err))?;
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                (
+                    settings,
+                    scene.camera,
+                    scene.environment,
+                    scene.output,
+                    scene.world,
+                    format!("{}#{:016x}", name, fingerprint(&source)),
+                )
+            }
+            (None, Some(entry)) => {
+                let mut settings = RenderSettings::default();
+                args.settings.apply(&mut settings)?;
+                let mut world = HittableList::new();
+                world.add(Arc::new(LinearBvh::new((entry.build)(settings.seed))));
+                (
+                    settings,
+                    (entry.camera)(),
+                    (entry.environment)(),
+                    OutputSettings::default(),
+                    world,
+                    entry.name.to_string(),
+                )
+            }
+            (None, None) => unreachable!("clap requires a scene or a file"),
+        };
 
-            render(&world, &cam, &settings)
-        }
-        2 => {
-            let mut world = HittableList::new();
-            world.add(Arc::new(Bvh::new(two_sphere())));
+    settings.validate()?;
+
+    args.camera.apply(&mut camera)?;
+    args.environment.apply(&mut environment)?;
+    let cam = camera.aspect_ratio(settings.aspect_ratio).build();
 
-            // Camera
-            let look_from = point!(13.0, 2.0, 3.0);
-            let look_at = point!(0.0, 0.0, 0.0);
+    args.output_settings.apply(&mut output);
 
-            let cam = camera::Builder::new(look_from, look_at, 20.0, 0.1, 10.0)
-                .tilted(vec3!(0.0, 1.0, 0.0))
-                .build();
+    if args.checkpoint.is_some() && settings.progressive.is_none() {
+        settings.progressive = Some(Progressive::default());
+    }
 
-            render(&world, &cam, &settings)
+    let aovs = &args.output_settings.aov;
+    let mut accumulation = match &args.checkpoint {
+        Some(path) if args.resume => {
+            Accumulation::load_checkpoint(path, &settings, &scene_id, aovs)
+                .map_err(|err| format!("cannot resume from {}: {}", path.display(), err))?
         }
-        _ => unimplemented!(),
+        _ => Accumulation::new(&settings, aovs),
     };
 
-    let file = File::create("test.png").unwrap();
-    let writer = BufWriter::new(file);
-    let mut encoder = png::Encoder::new(
-        writer,
-        settings.image_width as u32,
-        settings.image_height as u32,
-    );
-    encoder.set_color(png::ColorType::Rgb);
-    encoder.set_depth(png::BitDepth::Eight);
-
-    let mut writer = encoder.write_header().unwrap();
-
-    writer.write_image_data(&pixels).unwrap();
-}
+    let progress: Box<dyn ProgressSink> = match args.progress {
+        Progress::Bar => Box::<progress::Bar>::default(),
+        Progress::Json => Box::new(progress::Json::new(io::stdout())),
+        Progress::Silent => Box::new(progress::Silent),
+    };
 
-fn colorize(color: Color, spp: usize) -> String {
-    let scale = 1.0 / spp as f64;
-    let r = (color.x() * scale).sqrt();
-    let g = (color.y() * scale).sqrt();
-    let b = (color.z() * scale).sqrt();
-
-    format!(
-        "{} {} {}",
-        (256.0 * utils::clamp(r, 0.0, 0.999)) as usize,
-        (256.0 * utils::clamp(g, 0.0, 0.999)) as usize,
-        (256.0 * utils::clamp(b, 0.0, 0.999)) as usize
-    )
+    let mut passes = 0;
+    render_progressive(
+        &world,
+        &environment,
+        &cam,
+        &settings,
+        &mut accumulation,
+        progress.as_ref(),
+        &CancelToken::new(),
+        |accumulation: &Accumulation| -> Result<(), Box<dyn Error>> {
+            passes += 1;
+            if let Some(path) = &args.checkpoint {
+                accumulation
+                    .save_checkpoint(path, &settings, &scene_id)
+                    .map_err(|err| format!("cannot write {}: {}", path.display(), err))?;
+            }
+            if settings.progressive.is_some() {
+                write_image(accumulation, &settings, &output, args)?;
+            }
+            Ok(())
+        },
+    )?;
+
+    // Without passes the image is only written at the end, and a resumed
+    // render that was already complete has no pass left.
+    if settings.progressive.is_none() || passes == 0 {
+        write_image(&accumulation, &settings, &output, args)?;
+    }
+    Ok(())
 }
 
-struct HittableList {
-    objects: Vec<Arc<dyn Hittable>>,
-    bbox: Aabb,
+fn write_image(
+    accumulation: &Accumulation,
+    settings: &RenderSettings,
+    output: &OutputSettings,
+    args: &RenderArgs,
+) -> Result<(), Box<dyn Error>> {
+    let (film, aovs) = accumulation.finish(settings);
+    save(&film, &aovs, output, args)
+        .map_err(|err| format!("cannot write {}: {}", args.output.display(), err).into())
 }
 
-impl HittableList {
-    pub fn new() -> Self {
-        HittableList {
-            objects: vec![],
-            bbox: Aabb::default(),
-        }
-    }
-
-    pub fn add(&mut self, object: Arc<dyn Hittable>) {
-        self.bbox = Aabb::surrounding(&self.bbox, &object.bounding_box());
-        self.objects.push(object);
+/// Write the film in the format matching the extension of the output path,
+/// along with the output variables.
+fn save(
+    film: &Film,
+    aovs: &AovFilms,
+    output: &OutputSettings,
+    args: &RenderArgs,
+) -> Result<(), Box<dyn Error>> {
+    let path = &args.output;
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default();
+    let format = args.output_settings.format;
+    if let ("exr" | "pfm" | "hdr", Some(format)) = (extension.as_str(), format) {
+        return Err(format!(
+            "`--format {}` does not apply to `{}` files",
+            format, extension
+        )
+        .into());
     }
-}
 
-impl Hittable for HittableList {
-    fn hit(&self, ray: Ray, ray_t: Interval) -> Option<Hit> {
-        let mut closest_so_far = ray_t.maximum;
-        let mut result = None;
-
-        for object in &self.objects {
-            if let Some(hit) = object.hit(ray, Interval::new(ray_t.minimum, closest_so_far)) {
-                closest_so_far = hit.t;
-                result = Some(hit);
+    match extension.as_str() {
+        "exr" => {
+            let mut layers = vec![("", film)];
+            layers.extend(aovs.iter().map(|(aov, film)| (aov.name(), film)));
+            film::save_exr_layers(path, &layers, args.output_settings.exr_precision())?;
+        }
+        "pfm" => {
+            film.save_pfm(path)?;
+            for (aov, film) in aovs.iter() {
+                film.save_pfm(aov_path(path, aov))?;
+            }
+        }
+        "hdr" => {
+            film.save_hdr(path)?;
+            for (aov, film) in aovs.iter() {
+                film.save_hdr(aov_path(path, aov))?;
+            }
+        }
+        _ => {
+            let format = format
+                .or_else(|| ImageFormat::from_extension(&extension))
+                .unwrap_or_default();
+            let writer = format.writer();
+            writer.save(&film.develop(output), path)?;
+            for (aov, film) in aovs.iter() {
+                writer.save(&aov.preview(film), &aov_path(path, aov))?;
             }
         }
-
-        result
     }
+    Ok(())
+}
+
+/// FNV-1a hash of `bytes`, identifying the scene files in the checkpoints.
+fn fingerprint(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
 
-    fn bounding_box(&self) -> Aabb {
-        self.bbox
+/// Path of the file of an output variable, like `output.normal.png` for
+/// `output.png`.
+fn aov_path(path: &Path, aov: Aov) -> PathBuf {
+    let mut name = path.file_stem().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(aov.name());
+    if let Some(extension) = path.extension() {
+        name.push(".");
+        name.push(extension);
     }
+    path.with_file_name(name)
 }
@@ -1,11 +1,56 @@
 use crate::hit::Hit;
 use crate::ray::Ray;
+use crate::sampler::Sampler;
 use crate::vec3::Color;
+use std::sync::Arc;
 
 pub mod dielectric;
+pub mod diffuse_light;
 pub mod lambertian;
 pub mod metal;
 
 pub trait Material: Sync + Send {
-    fn scatter(&self, r_in: &Ray, hit: &Hit) -> Option<(Ray, Color)>;
+    /// Scatter `r_in` off the surface, drawing the random decisions from
+    /// `sampler`.
+    fn scatter(&self, r_in: &Ray, hit: &Hit, sampler: &mut dyn Sampler) -> Option<(Ray, Color)>;
+
+    /// Light emitted by the surface at the hit point, none by default.
+    ///
+    /// The whole hit is given rather than its surface coordinates and
+    /// position, as textured lights look up their texture like the other
+    /// materials, the mappings needing the normal and the filtering the
+    /// footprint of the hit.
+    fn emitted(&self, _hit: &Hit) -> Color {
+        Color::default()
+    }
+
+    /// Base color of the surface at the hit point, independent of the
+    /// lighting, none by default.
+    fn albedo(&self, _hit: &Hit) -> Color {
+        Color::default()
+    }
+
+    /// Identify the material, the same for all the objects sharing it.
+    fn id(&self) -> usize {
+        self as *const Self as *const () as usize
+    }
+}
+
+/// Allow a single material to be shared between several objects.
+impl<M: Material + ?Sized> Material for Arc<M> {
+    fn scatter(&self, r_in: &Ray, hit: &Hit, sampler: &mut dyn Sampler) -> Option<(Ray, Color)> {
+        (**self).scatter(r_in, hit, sampler)
+    }
+
+    fn emitted(&self, hit: &Hit) -> Color {
+        (**self).emitted(hit)
+    }
+
+    fn albedo(&self, hit: &Hit) -> Color {
+        (**self).albedo(hit)
+    }
+
+    fn id(&self) -> usize {
+        (**self).id()
+    }
 }
@@ -1,7 +1,7 @@
 use super::Material;
 use crate::hit::Hit;
 use crate::ray::Ray;
-use crate::utils::random;
+use crate::sampler::Sampler;
 use crate::vec3::{dot, reflect, refract, unit_vector, Color};
 
 pub struct Dielectric {
@@ -15,7 +15,7 @@ impl Dielectric {
 }
 
 impl Material for Dielectric {
-    fn scatter(&self, r_in: &Ray, hit: &Hit) -> Option<(Ray, Color)> {
+    fn scatter(&self, r_in: &Ray, hit: &Hit, sampler: &mut dyn Sampler) -> Option<(Ray, Color)> {
         let attenuation = color!(1.0, 1.0, 1.0);
         let refraction_ratio = if hit.front_face {
             1.0 / self.refraction_index
@@ -26,17 +26,21 @@ impl Material for Dielectric {
         let cos_theta = f64::min(dot(-unit_direction, hit.normal), 1.0);
         let sin_theta = (1.0 - cos_theta * cos_theta).abs().sqrt();
         let cannot_refract = refraction_ratio * sin_theta > 1.0;
+        let choice = sampler.get_1d();
 
-        let direction =
-            if cannot_refract || reflectance(cos_theta, refraction_ratio) > random(0.0..=1.0) {
-                reflect(unit_direction, hit.normal)
-            } else {
-                refract(unit_direction, hit.normal, refraction_ratio)
-            };
+        let direction = if cannot_refract || reflectance(cos_theta, refraction_ratio) > choice {
+            reflect(unit_direction, hit.normal)
+        } else {
+            refract(unit_direction, hit.normal, refraction_ratio)
+        };
 
         let scattered = Ray::new(hit.p, direction).timed(r_in.time());
         Some((scattered, attenuation))
     }
+
+    fn albedo(&self, _: &Hit) -> Color {
+        color!(1.0, 1.0, 1.0)
+    }
 }
 
 fn reflectance(cosine: f64, ref_idx: f64) -> f64 {
@@ -0,0 +1,44 @@
+use super::Material;
+use crate::hit::Hit;
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::texture::solid_color::SolidColor;
+use crate::texture::Texture;
+use crate::vec3::Color;
+
+/// A surface emitting light evenly in every direction without reflecting any.
+pub struct DiffuseLight {
+    pub emit: Box<dyn Texture>,
+}
+
+impl DiffuseLight {
+    pub fn new(emit: Color) -> Self {
+        DiffuseLight {
+            emit: Box::new(SolidColor::from(emit)),
+        }
+    }
+
+    pub fn textured(texture: Box<dyn Texture>) -> Self {
+        DiffuseLight { emit: texture }
+    }
+}
+
+impl Material for DiffuseLight {
+    fn scatter(&self, _: &Ray, _: &Hit, _: &mut dyn Sampler) -> Option<(Ray, Color)> {
+        None
+    }
+
+    fn emitted(&self, hit: &Hit) -> Color {
+        self.emit.value_at(hit)
+    }
+
+    /// The emitted color, brought back to the range of a reflectance.
+    fn albedo(&self, hit: &Hit) -> Color {
+        let emitted = self.emitted(hit);
+        color!(
+            emitted.x().min(1.0),
+            emitted.y().min(1.0),
+            emitted.z().min(1.0)
+        )
+    }
+}
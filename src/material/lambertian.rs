@@ -1,10 +1,10 @@
 use super::Material;
 use crate::hit::Hit;
 use crate::ray::Ray;
+use crate::sampler::{self, Sampler};
 use crate::texture::solid_color::SolidColor;
 use crate::texture::Texture;
-use crate::vec3::{Color, Vec3};
-use std::sync::Arc;
+use crate::vec3::Color;
 
 pub struct Lambertian {
     pub albedo: Box<dyn Texture>,
@@ -23,13 +23,17 @@ impl Lambertian {
 }
 
 impl Material for Lambertian {
-    fn scatter(&self, r_in: &Ray, hit: &Hit) -> Option<(Ray, Color)> {
-        let mut scatter_direction = hit.normal + Vec3::random_unit();
+    fn scatter(&self, r_in: &Ray, hit: &Hit, sampler: &mut dyn Sampler) -> Option<(Ray, Color)> {
+        let mut scatter_direction = hit.normal + sampler::sample_sphere(sampler.get_2d());
         if scatter_direction.near_zero() {
             scatter_direction = hit.normal;
         }
 
         let scattered = Ray::new(hit.p, scatter_direction).timed(r_in.time());
-        Some((scattered, self.albedo.value(hit.u, hit.v, hit.p)))
+        Some((scattered, self.albedo.value_at(hit)))
+    }
+
+    fn albedo(&self, hit: &Hit) -> Color {
+        self.albedo.value_at(hit)
     }
 }
@@ -1,7 +1,8 @@
 use super::Material;
 use crate::hit::Hit;
 use crate::ray::Ray;
-use crate::vec3::{dot, reflect, unit_vector, Color, Vec3};
+use crate::sampler::{self, Sampler};
+use crate::vec3::{dot, reflect, unit_vector, Color};
 
 pub struct Metal {
     pub albedo: Color,
@@ -18,17 +19,18 @@ impl Metal {
 }
 
 impl Material for Metal {
-    fn scatter(&self, r_in: &Ray, hit: &Hit) -> Option<(Ray, Color)> {
+    fn scatter(&self, r_in: &Ray, hit: &Hit, sampler: &mut dyn Sampler) -> Option<(Ray, Color)> {
         let reflected = reflect(unit_vector(r_in.direction()), hit.normal);
-        let scattered = Ray::new(
-            hit.p,
-            reflected + self.fuzziness * Vec3::random_in_unit_sphere(),
-        )
-        .timed(r_in.time());
+        let fuzz = sampler::sample_ball(sampler.get_2d(), sampler.get_1d());
+        let scattered = Ray::new(hit.p, reflected + self.fuzziness * fuzz).timed(r_in.time());
         if dot(scattered.direction(), hit.normal) > 0.0 {
             Some((scattered, self.albedo))
         } else {
             None
         }
     }
+
+    fn albedo(&self, _: &Hit) -> Color {
+        self.albedo
+    }
 }
@@ -0,0 +1,479 @@
+//! Wavefront OBJ and MTL import.
+//!
+//! Faces are triangulated as fans and split into one `TriangleMesh` per group
+//! and material. MTL materials are mapped onto the existing materials:
+//! transparent ones (`d` < 1, `Tr` > 0 or a refraction `illum` model) become
+//! `Dielectric` using `Ni`, reflective ones (`illum` 3, 5 or 8) become `Metal`
+//! using `Kd` as albedo and `Ns` for the fuzziness, emissive ones (non-black
+//! `Ke`) become `DiffuseLight`, everything else is `Lambertian` using `Kd`.
+
+use crate::geometry::mesh::{Face, TriangleMesh};
+use crate::hittable_list::HittableList;
+use crate::material::dielectric::Dielectric;
+use crate::material::diffuse_light::DiffuseLight;
+use crate::material::lambertian::Lambertian;
+use crate::material::metal::Metal;
+use crate::material::Material;
+use crate::vec3::{Color, Point3, Vec3};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::SplitWhitespace;
+use std::sync::Arc;
+
+#[derive(Debug)]
+pub enum ObjError {
+    Io {
+        path: PathBuf,
+        source: io::Error,
+    },
+    Parse {
+        path: PathBuf,
+        line: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ObjError::Io { path, source } => {
+                write!(f, "cannot read {}: {}", path.display(), source)
+            }
+            ObjError::Parse {
+                path,
+                line,
+                message,
+            } => write!(f, "{}:{}: {}", path.display(), line, message),
+        }
+    }
+}
+
+impl Error for ObjError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ObjError::Io { source, .. } => Some(source),
+            ObjError::Parse { .. } => None,
+        }
+    }
+}
+
+/// The faces of a group sharing a single material.
+pub struct Group {
+    pub name: String,
+    pub material: Option<String>,
+    pub mesh: TriangleMesh<Arc<dyn Material>>,
+}
+
+pub struct Model {
+    pub groups: Vec<Group>,
+}
+
+impl Model {
+    pub fn triangle_count(&self) -> usize {
+        self.groups
+            .iter()
+            .map(|group| group.mesh.triangle_count())
+            .sum()
+    }
+
+    pub fn into_list(self) -> HittableList {
+        let mut list = HittableList::new();
+        for group in self.groups {
+            list.add(Arc::new(group.mesh));
+        }
+        list
+    }
+}
+
+/// Faces collected for a (group, material) pair, indexing the whole file.
+struct Chunk {
+    group: String,
+    material: Option<String>,
+    faces: Vec<Face>,
+}
+
+/// Load an OBJ file along with the material libraries it references.
+///
+/// Faces without a material, or using a material missing from the libraries,
+/// use `default_material`.
+pub fn load<P: AsRef<Path>>(
+    path: P,
+    default_material: Arc<dyn Material>,
+) -> Result<Model, ObjError> {
+    let path = path.as_ref();
+    parse(&read(path)?, path, default_material)
+}
+
+/// Build the model described by `source`, the content of the OBJ file at
+/// `path`, which names it in the errors and locates the material libraries.
+pub fn parse(
+    source: &str,
+    path: &Path,
+    default_material: Arc<dyn Material>,
+) -> Result<Model, ObjError> {
+    let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let error = |line: usize, message: String| ObjError::Parse {
+        path: path.to_path_buf(),
+        line,
+        message,
+    };
+
+    let mut positions = vec![];
+    let mut normals = vec![];
+    let mut uvs = vec![];
+    let mut materials: HashMap<String, Arc<dyn Material>> = HashMap::new();
+    let mut chunks: Vec<Chunk> = vec![];
+    let mut group = String::from("default");
+    let mut material: Option<String> = None;
+
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let mut tokens = statement(line);
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+
+        match keyword {
+            // The optional `w` weight, or the vertex color some exporters
+            // write after the position, are ignored.
+            "v" => positions.push(Point3::from(
+                parse_floats::<3>(tokens.take(3), 3).map_err(|m| error(line_number, m))?,
+            )),
+            "vn" => normals.push(Vec3::from(
+                parse_floats::<3>(&mut tokens, 3).map_err(|m| error(line_number, m))?,
+            )),
+            "vt" => {
+                let [u, v, _] =
+                    parse_floats::<3>(&mut tokens, 1).map_err(|m| error(line_number, m))?;
+                uvs.push([u, v]);
+            }
+            "f" => {
+                let corners = tokens
+                    .map(|corner| parse_corner(corner, positions.len(), uvs.len(), normals.len()))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|m| error(line_number, m))?;
+                if corners.len() < 3 {
+                    return Err(error(
+                        line_number,
+                        format!("face needs at least 3 vertices, found {}", corners.len()),
+                    ));
+                }
+
+                let chunk = match chunks
+                    .iter()
+                    .position(|chunk| chunk.group == group && chunk.material == material)
+                {
+                    Some(position) => &mut chunks[position],
+                    None => {
+                        chunks.push(Chunk {
+                            group: group.clone(),
+                            material: material.clone(),
+                            faces: vec![],
+                        });
+                        chunks.last_mut().unwrap()
+                    }
+                };
+
+                for i in 1..corners.len() - 1 {
+                    let fan = [corners[0], corners[i], corners[i + 1]];
+                    let uvs = fan.iter().map(|c| c.1).collect::<Option<Vec<_>>>();
+                    let normals = fan.iter().map(|c| c.2).collect::<Option<Vec<_>>>();
+                    chunk.faces.push(Face {
+                        positions: fan.map(|c| c.0),
+                        uvs: uvs.map(|i| [i[0], i[1], i[2]]),
+                        normals: normals.map(|i| [i[0], i[1], i[2]]),
+                    });
+                }
+            }
+            "g" | "o" => {
+                let name = tokens.collect::<Vec<_>>().join(" ");
+                group = if name.is_empty() {
+                    String::from("default")
+                } else {
+                    name
+                };
+            }
+            "usemtl" => {
+                let name = tokens
+                    .next()
+                    .ok_or_else(|| error(line_number, String::from("missing material name")))?;
+                material = Some(name.to_string());
+            }
+            "mtllib" => {
+                for library in tokens {
+                    materials.extend(load_library(&base_dir.join(library))?);
+                }
+            }
+            // Smoothing groups, lines, points and free-form geometry are not
+            // supported and ignored.
+            _ => {}
+        }
+    }
+
+    let groups = chunks
+        .into_iter()
+        .filter(|chunk| !chunk.faces.is_empty())
+        .map(|chunk| {
+            let mesh_material = chunk
+                .material
+                .as_ref()
+                .and_then(|name| materials.get(name))
+                .cloned()
+                .unwrap_or_else(|| default_material.clone());
+            Group {
+                mesh: compact_mesh(&chunk.faces, &positions, &normals, &uvs, mesh_material),
+                name: chunk.group,
+                material: chunk.material,
+            }
+        })
+        .collect();
+
+    Ok(Model { groups })
+}
+
+fn read(path: &Path) -> Result<String, ObjError> {
+    fs::read_to_string(path).map_err(|source| ObjError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Split a line into tokens, dropping comments.
+fn statement(line: &str) -> SplitWhitespace<'_> {
+    line.split('#')
+        .next()
+        .unwrap_or_default()
+        .split_whitespace()
+}
+
+/// Parse up to `N` floats, at least `required` of them, missing ones are 0.
+fn parse_floats<'a, const N: usize>(
+    tokens: impl Iterator<Item = &'a str>,
+    required: usize,
+) -> Result<[f64; N], String> {
+    let mut values = [0.0; N];
+    let mut count = 0;
+    for token in tokens {
+        if count == N {
+            return Err(format!("expected at most {} numbers", N));
+        }
+        values[count] = token
+            .parse()
+            .map_err(|_| format!("invalid number `{}`", token))?;
+        count += 1;
+    }
+    if count < required {
+        return Err(format!("expected {} numbers, found {}", required, count));
+    }
+    Ok(values)
+}
+
+/// Resolve a 1-based, possibly negative, OBJ index into a buffer of `len`
+/// elements.
+fn resolve_index(token: &str, len: usize, kind: &str) -> Result<usize, String> {
+    let index: i64 = token
+        .parse()
+        .map_err(|_| format!("invalid {} index `{}`", kind, token))?;
+    let resolved = match index {
+        i if i > 0 => i - 1,
+        i if i < 0 => len as i64 + i,
+        _ => return Err(format!("{} index cannot be 0", kind)),
+    };
+    if resolved < 0 || resolved >= len as i64 {
+        return Err(format!(
+            "{} index {} out of range, {} defined so far",
+            kind, index, len
+        ));
+    }
+    Ok(resolved as usize)
+}
+
+/// Parse a face corner written as `v`, `v/vt`, `v//vn` or `v/vt/vn`.
+fn parse_corner(
+    corner: &str,
+    positions: usize,
+    uvs: usize,
+    normals: usize,
+) -> Result<(usize, Option<usize>, Option<usize>), String> {
+    let mut parts = corner.split('/');
+    let position = resolve_index(parts.next().unwrap_or_default(), positions, "vertex")?;
+    let uv = match parts.next() {
+        Some("") | None => None,
+        Some(token) => Some(resolve_index(token, uvs, "texture coordinate")?),
+    };
+    let normal = match parts.next() {
+        Some("") | None => None,
+        Some(token) => Some(resolve_index(token, normals, "normal")?),
+    };
+    if parts.next().is_some() {
+        return Err(format!("invalid face vertex `{}`", corner));
+    }
+    Ok((position, uv, normal))
+}
+
+/// Build a mesh holding only the attributes used by `faces`.
+fn compact_mesh(
+    faces: &[Face],
+    positions: &[Point3],
+    normals: &[Vec3],
+    uvs: &[[f64; 2]],
+    material: Arc<dyn Material>,
+) -> TriangleMesh<Arc<dyn Material>> {
+    fn remap<T: Copy>(
+        index: usize,
+        source: &[T],
+        target: &mut Vec<T>,
+        map: &mut HashMap<usize, usize>,
+    ) -> usize {
+        *map.entry(index).or_insert_with(|| {
+            target.push(source[index]);
+            target.len() - 1
+        })
+    }
+
+    let (mut mesh_positions, mut mesh_normals, mut mesh_uvs) = (vec![], vec![], vec![]);
+    let (mut position_map, mut normal_map, mut uv_map) =
+        (HashMap::new(), HashMap::new(), HashMap::new());
+
+    let faces = faces
+        .iter()
+        .map(|face| Face {
+            positions: face
+                .positions
+                .map(|i| remap(i, positions, &mut mesh_positions, &mut position_map)),
+            normals: face
+                .normals
+                .map(|n| n.map(|i| remap(i, normals, &mut mesh_normals, &mut normal_map))),
+            uvs: face
+                .uvs
+                .map(|t| t.map(|i| remap(i, uvs, &mut mesh_uvs, &mut uv_map))),
+        })
+        .collect();
+
+    TriangleMesh::new(mesh_positions, mesh_normals, mesh_uvs, faces, material)
+}
+
+#[derive(Default)]
+struct MtlDesc {
+    diffuse: Option<Color>,
+    emission: Option<Color>,
+    specular_exponent: Option<f64>,
+    refraction_index: Option<f64>,
+    dissolve: Option<f64>,
+    illumination: Option<u32>,
+}
+
+impl MtlDesc {
+    fn build(&self) -> Arc<dyn Material> {
+        let albedo = self.diffuse.unwrap_or(color!(0.8, 0.8, 0.8));
+        let transparent = self.dissolve.is_some_and(|d| d < 1.0)
+            || matches!(self.illumination, Some(4 | 6 | 7 | 9));
+        let reflective = matches!(self.illumination, Some(3 | 5 | 8));
+
+        if let Some(emission) = self.emission.filter(|e| !e.near_zero()) {
+            Arc::new(DiffuseLight::new(emission))
+        } else if transparent {
+            Arc::new(Dielectric::new(self.refraction_index.unwrap_or(1.5)))
+        } else if reflective {
+            // Ns goes from 0 (wide highlight) to 1000 (mirror-like).
+            let shininess = self.specular_exponent.unwrap_or(1000.0) / 1000.0;
+            Arc::new(Metal::new(albedo, 1.0 - shininess.clamp(0.0, 1.0)))
+        } else {
+            Arc::new(Lambertian::new(albedo))
+        }
+    }
+}
+
+/// Load the materials defined by an MTL library.
+pub fn load_library(path: &Path) -> Result<HashMap<String, Arc<dyn Material>>, ObjError> {
+    parse_library(&read(path)?, path)
+}
+
+/// Build the materials defined by `source`, the content of the MTL library at
+/// `path`.
+pub fn parse_library(
+    source: &str,
+    path: &Path,
+) -> Result<HashMap<String, Arc<dyn Material>>, ObjError> {
+    let error = |line: usize, message: String| ObjError::Parse {
+        path: path.to_path_buf(),
+        line,
+        message,
+    };
+
+    let mut descs: Vec<(String, MtlDesc)> = vec![];
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let mut tokens = statement(line);
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+
+        if keyword == "newmtl" {
+            let name = tokens
+                .next()
+                .ok_or_else(|| error(line_number, String::from("missing material name")))?;
+            descs.push((name.to_string(), MtlDesc::default()));
+            continue;
+        }
+
+        let known = matches!(keyword, "Kd" | "Ke" | "Ns" | "Ni" | "d" | "Tr" | "illum");
+        let desc = match descs.last_mut() {
+            Some((_, desc)) => desc,
+            None if known => {
+                return Err(error(
+                    line_number,
+                    format!("`{}` before any `newmtl`", keyword),
+                ))
+            }
+            None => continue,
+        };
+
+        let scalar = |tokens: &mut SplitWhitespace| {
+            parse_floats::<1>(tokens, 1)
+                .map(|[value]| value)
+                .map_err(|m| error(line_number, m))
+        };
+        match keyword {
+            "Kd" => {
+                let [r, g, b] =
+                    parse_floats::<3>(&mut tokens, 3).map_err(|m| error(line_number, m))?;
+                desc.diffuse = Some(color!(r, g, b));
+            }
+            "Ke" => {
+                let [r, g, b] =
+                    parse_floats::<3>(&mut tokens, 3).map_err(|m| error(line_number, m))?;
+                desc.emission = Some(color!(r, g, b));
+            }
+            "Ns" => desc.specular_exponent = Some(scalar(&mut tokens)?),
+            "Ni" => desc.refraction_index = Some(scalar(&mut tokens)?),
+            "d" => desc.dissolve = Some(scalar(&mut tokens)?),
+            "Tr" => desc.dissolve = Some(1.0 - scalar(&mut tokens)?),
+            "illum" => {
+                let value = tokens.next().unwrap_or_default();
+                desc.illumination = Some(value.parse().map_err(|_| {
+                    error(
+                        line_number,
+                        format!("invalid illumination model `{}`", value),
+                    )
+                })?);
+            }
+            // Other properties (Ka, Ks, maps, ...) have no equivalent yet.
+            _ => {}
+        }
+    }
+
+    Ok(descs
+        .into_iter()
+        .map(|(name, desc)| {
+            let material = desc.build();
+            (name, material)
+        })
+        .collect())
+}
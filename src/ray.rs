@@ -5,6 +5,17 @@ pub struct Ray {
     origin: Point3,
     direction: Vec3,
     time: f64,
+    differentials: Option<Differentials>,
+}
+
+/// The rays through the next pixel to the right (`x`) and above (`y`) of a
+/// camera ray, telling how far apart neighboring pixels land.
+#[derive(Copy, Clone)]
+pub struct Differentials {
+    pub x_origin: Point3,
+    pub x_direction: Vec3,
+    pub y_origin: Point3,
+    pub y_direction: Vec3,
 }
 
 impl Ray {
@@ -13,6 +24,7 @@ impl Ray {
             origin,
             direction,
             time: 0.0,
+            differentials: None,
         }
     }
 
@@ -21,6 +33,25 @@ impl Ray {
         self
     }
 
+    pub fn with_differentials(mut self, differentials: Differentials) -> Self {
+        self.differentials = Some(differentials);
+        self
+    }
+
+    /// Bring the differentials closer to the ray by `scale`, for pixels
+    /// sampled several times where each sample covers a fraction of the
+    /// pixel.
+    pub fn scale_differentials(mut self, scale: f64) -> Self {
+        if let Some(differentials) = &mut self.differentials {
+            let (origin, direction) = (self.origin, self.direction);
+            differentials.x_origin = origin + scale * (differentials.x_origin - origin);
+            differentials.x_direction = direction + scale * (differentials.x_direction - direction);
+            differentials.y_origin = origin + scale * (differentials.y_origin - origin);
+            differentials.y_direction = direction + scale * (differentials.y_direction - direction);
+        }
+        self
+    }
+
     pub fn origin(&self) -> Point3 {
         self.origin
     }
@@ -33,6 +64,11 @@ impl Ray {
         self.time
     }
 
+    /// The differentials of camera rays, none for the other rays.
+    pub fn differentials(&self) -> Option<&Differentials> {
+        self.differentials.as_ref()
+    }
+
     pub fn at(&self, t: f64) -> Point3 {
         self.origin + t * self.direction
     }
@@ -1,13 +1,32 @@
 use crate::aabb::Interval;
+use crate::aov::{Aov, AovFilms};
+use crate::denoise::Denoiser;
+use crate::hit::Hit;
 use crate::ray::Ray;
-use crate::vec3::unit_vector;
+use crate::sampler::{Sampler, SamplerKind};
 use crate::Camera;
 use crate::Color;
+use crate::Environment;
+use crate::Film;
 use crate::Hittable;
-use crate::HittableList;
-use indicatif::{ProgressBar, ProgressStyle};
-use rand::distributions::{Distribution, Uniform};
 use rayon::prelude::*;
+use std::error::Error;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+pub mod adaptive;
+pub mod cancel;
+pub mod progress;
+pub mod progressive;
+pub mod tile;
+
+use adaptive::AdaptiveSampling;
+use cancel::CancelToken;
+use progress::{ProgressSink, Silent};
+use progressive::{Accumulation, Progressive};
+use tile::TileOrder;
 
 #[derive(Copy, Clone)]
 pub struct RenderSettings {
@@ -16,6 +35,19 @@ pub struct RenderSettings {
     pub image_width: usize,
     pub sample_per_pixel: usize,
     pub max_depth: usize,
+    /// Seed of the random streams, the same seed giving the same image.
+    pub seed: u64,
+    pub sampler: SamplerKind,
+    /// Filter applied to the rendered film.
+    pub denoiser: Denoiser,
+    /// Keep sampling the noisy pixels after the first `sample_per_pixel`
+    /// samples, in batches of that size.
+    pub adaptive: Option<AdaptiveSampling>,
+    /// Render in several passes instead of taking all the samples at once.
+    pub progressive: Option<Progressive>,
+    /// Side of the square tiles handed out to the threads, in pixels.
+    pub tile_size: usize,
+    pub tile_order: TileOrder,
 }
 
 impl RenderSettings {
@@ -28,6 +60,36 @@ impl RenderSettings {
             ..Default::default()
         }
     }
+
+    /// Change the image dimensions, keeping the other settings.
+    pub fn resize(&mut self, image_width: usize, aspect_ratio: f64) {
+        self.aspect_ratio = aspect_ratio;
+        self.image_width = image_width;
+        self.image_height = (image_width as f64 / aspect_ratio) as usize;
+    }
+
+    /// Check that an image can be rendered with these settings.
+    pub fn validate(&self) -> Result<(), SettingsError> {
+        if self.image_width < 2 || self.image_height < 2 {
+            return Err(SettingsError::ImageTooSmall {
+                width: self.image_width,
+                height: self.image_height,
+            });
+        }
+        if self.sample_per_pixel == 0 {
+            return Err(SettingsError::NoSamples);
+        }
+        if self
+            .progressive
+            .is_some_and(|progressive| progressive.pass_samples == 0)
+        {
+            return Err(SettingsError::NoPassSamples);
+        }
+        if self.tile_size == 0 {
+            return Err(SettingsError::NoTileSize);
+        }
+        Ok(())
+    }
 }
 
 impl Default for RenderSettings {
@@ -41,69 +103,333 @@ impl Default for RenderSettings {
             image_height,
             sample_per_pixel: 300,
             max_depth: 300,
+            seed: 0,
+            sampler: SamplerKind::default(),
+            denoiser: Denoiser::default(),
+            adaptive: None,
+            progressive: None,
+            tile_size: 32,
+            tile_order: TileOrder::default(),
+        }
+    }
+}
+
+/// Settings with which no image can be rendered.
+#[derive(Debug)]
+pub enum SettingsError {
+    /// Each side of the image needs 2 pixels, the first and the last one
+    /// sitting on the edges of the viewport.
+    ImageTooSmall {
+        width: usize,
+        height: usize,
+    },
+    NoSamples,
+    NoPassSamples,
+    NoTileSize,
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SettingsError::ImageTooSmall { width, height } => write!(
+                f,
+                "image of {}x{} pixels, at least 2 are needed on each side",
+                width, height
+            ),
+            SettingsError::NoSamples => write!(f, "at least 1 sample per pixel is needed"),
+            SettingsError::NoPassSamples => {
+                write!(f, "at least 1 sample per progressive pass is needed")
+            }
+            SettingsError::NoTileSize => write!(f, "tiles must be at least 1 pixel wide"),
+        }
+    }
+}
+
+impl Error for SettingsError {}
+
+pub fn render(
+    world: &impl Hittable,
+    environment: &Environment,
+    cam: &Camera,
+    settings: &RenderSettings,
+) -> Result<Film, SettingsError> {
+    Ok(render_with_aovs(world, environment, cam, settings, &[])?.0)
+}
+
+/// Render the image along with the requested output variables, without
+/// reporting the progress.
+pub fn render_with_aovs(
+    world: &impl Hittable,
+    environment: &Environment,
+    cam: &Camera,
+    settings: &RenderSettings,
+    aovs: &[Aov],
+) -> Result<(Film, AovFilms), SettingsError> {
+    let partial = render_cancellable(world, environment, cam, settings, aovs, &CancelToken::new())?;
+    Ok((partial.film, partial.aovs))
+}
+
+/// What a render produced before it ended or was cancelled.
+pub struct PartialRender {
+    pub film: Film,
+    pub aovs: AovFilms,
+    /// Whether each pixel received all its samples, row by row starting from
+    /// the top left corner. The other pixels have fewer samples, or none and
+    /// are black.
+    pub completed: Vec<bool>,
+}
+
+impl PartialRender {
+    pub fn is_complete(&self) -> bool {
+        self.completed.iter().all(|&completed| completed)
+    }
+}
+
+/// Render the image along with the requested output variables until done or
+/// until `cancel` is cancelled, whichever comes first.
+///
+/// The film of a cancelled render is not denoised, the missing pixels would
+/// bleed into the others.
+pub fn render_cancellable(
+    world: &impl Hittable,
+    environment: &Environment,
+    cam: &Camera,
+    settings: &RenderSettings,
+    aovs: &[Aov],
+    cancel: &CancelToken,
+) -> Result<PartialRender, SettingsError> {
+    let mut accumulation = Accumulation::new(settings, aovs);
+    render_progressive(
+        world,
+        environment,
+        cam,
+        settings,
+        &mut accumulation,
+        &Silent,
+        cancel,
+        |_| Ok(()),
+    )?;
+
+    let completed = accumulation.completed(target_samples(settings));
+    let (film, aovs) = if completed.iter().all(|&completed| completed) {
+        accumulation.finish(settings)
+    } else {
+        accumulation.finish(&RenderSettings {
+            denoiser: Denoiser::Off,
+            ..*settings
+        })
+    };
+    Ok(PartialRender {
+        film,
+        aovs,
+        completed,
+    })
+}
+
+/// Total number of samples of the pixels that do not converge.
+pub fn target_samples(settings: &RenderSettings) -> usize {
+    let samples = settings.sample_per_pixel;
+    settings
+        .adaptive
+        .map_or(samples, |adaptive| adaptive.max_samples.max(samples))
+}
+
+/// Add samples to `accumulation` pass after pass until the target number of
+/// samples or the time budget of `settings.progressive` is reached, calling
+/// `on_pass` after each pass. Without progressive settings, a single pass
+/// takes all the samples.
+///
+/// When `cancel` is cancelled, the pass in progress stops without calling
+/// `on_pass`, leaving the pixels it did not reach with fewer samples.
+///
+/// Invalid `settings` are reported before rendering anything.
+#[allow(clippy::too_many_arguments)]
+pub fn render_progressive<E: From<SettingsError>>(
+    world: &impl Hittable,
+    environment: &Environment,
+    cam: &Camera,
+    settings: &RenderSettings,
+    accumulation: &mut Accumulation,
+    progress: &dyn ProgressSink,
+    cancel: &CancelToken,
+    mut on_pass: impl FnMut(&Accumulation) -> Result<(), E>,
+) -> Result<(), E> {
+    settings.validate()?;
+    let start = Instant::now();
+    let target = target_samples(settings);
+    let progressive = settings.progressive.unwrap_or(Progressive {
+        pass_samples: target,
+        time_budget: None,
+    });
+
+    while accumulation.samples() < target && !accumulation.converged() {
+        let samples = (accumulation.samples() + progressive.pass_samples).min(target);
+        let finished = render_pass(
+            world,
+            environment,
+            cam,
+            settings,
+            accumulation,
+            samples,
+            progress,
+            cancel,
+        );
+        if !finished {
+            break;
+        }
+        on_pass(accumulation)?;
+        if progressive
+            .time_budget
+            .is_some_and(|budget| start.elapsed() >= budget)
+        {
+            break;
         }
     }
+    Ok(())
 }
 
-pub fn render(world: &HittableList, cam: &Camera, settings: &RenderSettings) -> Vec<u8> {
-    let bar = &Box::new(ProgressBar::new(
-        (settings.image_width * settings.image_height) as u64,
-    ));
-    bar.set_prefix("Render in progress");
-    bar.set_style(
-        ProgressStyle::default_bar()
-            .template("{prefix:.white} {bar:40.cyan/blue} {percent}% [{eta_precise}]"),
-    );
-
-    let result = (0..settings.image_height)
+/// Bring the pixels of `accumulation` to `samples` samples, unless adaptive
+/// sampling finds them converged. Returns `false` if `cancel` stopped the
+/// pass before the end.
+///
+/// `settings` must be valid, see `RenderSettings::validate`.
+#[allow(clippy::too_many_arguments)]
+pub fn render_pass(
+    world: &impl Hittable,
+    environment: &Environment,
+    cam: &Camera,
+    settings: &RenderSettings,
+    accumulation: &mut Accumulation,
+    samples: usize,
+    progress: &dyn ProgressSink,
+    cancel: &CancelToken,
+) -> bool {
+    let (width, height) = (accumulation.width(), accumulation.height());
+    let tiles = tile::tiles(width, height, settings.tile_size, settings.tile_order);
+    progress.pass_started(samples, &tiles);
+
+    let with_aovs = accumulation.with_aovs();
+    let max_samples = target_samples(settings);
+    // Spacing of the pixels on the viewport, and the share of it covered by
+    // each sample.
+    let pixel_size = (1.0 / (width - 1) as f64, 1.0 / (height - 1) as f64);
+    let footprint_scale = (1.0 / (settings.sample_per_pixel as f64).sqrt()).max(0.125);
+    let next_tile = AtomicUsize::new(0);
+    let interrupted = AtomicBool::new(false);
+    let pixels = Mutex::new(&mut accumulation.pixels);
+    // Each worker takes the next tile in order until there are none left.
+    (0..rayon::current_num_threads())
         .into_par_iter()
-        .rev()
-        .flat_map(|j| {
-            (0..settings.image_width)
-                .into_par_iter()
-                .flat_map(move |i| {
-                    let mut pixel_color = Color::default();
-                    let mut rng = rand::thread_rng();
-                    let dist = Uniform::from(0.0..=1.0);
-
-                    for _ in 0..settings.sample_per_pixel {
-                        let u =
-                            (i as f64 + dist.sample(&mut rng)) / (settings.image_width - 1) as f64;
-                        let v =
-                            (j as f64 + dist.sample(&mut rng)) / (settings.image_height - 1) as f64;
-                        let ray = cam.get_ray(u, v);
-                        pixel_color += ray_color(ray, world, settings.max_depth);
+        .for_each(|_| {
+            let mut sampler = settings.sampler.create(max_samples, settings.seed);
+            while let Some(tile) = tiles.get(next_tile.fetch_add(1, Ordering::Relaxed)) {
+                if cancel.is_cancelled() {
+                    interrupted.store(true, Ordering::Relaxed);
+                    break;
+                }
+                let index = |(x, y)| y * width + x;
+                let mut states: Vec<_> = {
+                    let pixels = pixels.lock().unwrap();
+                    tile.pixels().map(|pixel| pixels[index(pixel)]).collect()
+                };
+                let mut rendered = 0;
+                for ((x, y), pixel) in tile.pixels().zip(&mut states) {
+                    if cancel.is_cancelled() {
+                        break;
                     }
-                    bar.inc(1);
-                    pixel_color /= settings.sample_per_pixel as f64;
-                    pixel_color = color!(
-                        pixel_color[0].sqrt(),
-                        pixel_color[1].sqrt(),
-                        pixel_color[2].sqrt()
-                    );
-                    (0..3)
-                        .into_par_iter()
-                        .map(move |k| (255.999 * pixel_color[k as usize]).min(255.0) as u8)
-                })
-        })
-        .collect();
-    bar.finish();
+                    rendered += 1;
+                    // The camera counts the rows from the bottom.
+                    let (i, j) = (x, height - 1 - y);
+                    for sample in pixel.stats.count()..samples {
+                        if pixel.converged {
+                            break;
+                        }
+                        sampler.start_pixel_sample(i, j, sample);
+                        let (du, dv) = sampler.get_2d();
+                        let u = (i as f64 + du) / (width - 1) as f64;
+                        let v = (j as f64 + dv) / (height - 1) as f64;
+                        let ray = cam
+                            .get_ray_differential(u, v, pixel_size, sampler.as_mut())
+                            .scale_differentials(footprint_scale);
+                        let hit = world
+                            .hit(ray, Interval::new(0.001, f64::INFINITY))
+                            .map(|hit| hit.with_footprint(&ray));
+                        if with_aovs {
+                            pixel.aovs.add(&ray, hit.as_ref());
+                        }
+                        pixel.stats.add(match hit {
+                            Some(hit) => shade(
+                                &ray,
+                                &hit,
+                                world,
+                                environment,
+                                sampler.as_mut(),
+                                settings.max_depth,
+                            ),
+                            None => environment.value(ray.direction()),
+                        });
+
+                        let end_of_batch = (sample + 1) % settings.sample_per_pixel == 0;
+                        pixel.converged = end_of_batch
+                            && settings
+                                .adaptive
+                                .is_some_and(|adaptive| adaptive.converged(&pixel.stats));
+                    }
+                }
+                {
+                    let mut pixels = pixels.lock().unwrap();
+                    for (pixel, state) in tile.pixels().zip(states).take(rendered) {
+                        pixels[index(pixel)] = state;
+                    }
+                }
+                if rendered < tile.pixel_count() {
+                    interrupted.store(true, Ordering::Relaxed);
+                    break;
+                }
+                progress.tile_finished(tile);
+            }
+        });
 
-    result
+    if interrupted.into_inner() {
+        progress.pass_cancelled(samples);
+        return false;
+    }
+    progress.pass_finished(samples);
+    accumulation.set_samples(samples);
+    true
 }
 
-fn ray_color(ray: Ray, world: &impl Hittable, depth: usize) -> Color {
+fn ray_color(
+    ray: Ray,
+    world: &impl Hittable,
+    environment: &Environment,
+    sampler: &mut dyn Sampler,
+    depth: usize,
+) -> Color {
     if depth == 0 {
         return Color::default();
     }
 
-    if let Some(hit) = world.hit(ray, Interval::new(0.001, f64::INFINITY)) {
-        if let Some((scattered, attenuation)) = hit.material.scatter(&ray, &hit) {
-            return attenuation * ray_color(scattered, world, depth - 1);
+    match world.hit(ray, Interval::new(0.001, f64::INFINITY)) {
+        Some(hit) => shade(&ray, &hit, world, environment, sampler, depth),
+        None => environment.value(ray.direction()),
+    }
+}
+
+/// Light leaving the surface at `hit` towards the origin of `ray`.
+fn shade(
+    ray: &Ray,
+    hit: &Hit,
+    world: &impl Hittable,
+    environment: &Environment,
+    sampler: &mut dyn Sampler,
+    depth: usize,
+) -> Color {
+    let emitted = hit.material.emitted(hit);
+    match hit.material.scatter(ray, hit, sampler) {
+        Some((scattered, attenuation)) if depth > 1 => {
+            emitted + attenuation * ray_color(scattered, world, environment, sampler, depth - 1)
         }
-        return Color::default();
+        _ => emitted,
     }
-    let unit_direction = unit_vector(ray.direction());
-    let t = 0.5 * (unit_direction.y() + 1.0);
-    (1.0 - t) * color!(1.0, 1.0, 1.0) + t * color!(0.5, 0.7, 1.0)
 }
@@ -0,0 +1,92 @@
+//! Distribution of the samples according to the noise of each pixel: pixels
+//! keep being sampled until the estimated error of their value is small
+//! enough.
+
+use crate::tonemap;
+use crate::vec3::Color;
+
+/// Smallest luminance the error is taken relative to, so that dark pixels
+/// are not sampled endlessly.
+const LUMINANCE_FLOOR: f64 = 0.01;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AdaptiveSampling {
+    /// Standard error of the luminance of a pixel, relative to the luminance,
+    /// below which sampling stops.
+    pub threshold: f64,
+    /// Number of samples after which noisy pixels are left as they are.
+    pub max_samples: usize,
+}
+
+impl AdaptiveSampling {
+    pub fn new(threshold: f64, max_samples: usize) -> Self {
+        AdaptiveSampling {
+            threshold,
+            max_samples,
+        }
+    }
+
+    pub fn converged(&self, stats: &PixelStats) -> bool {
+        stats.relative_error() < self.threshold
+    }
+}
+
+/// Running mean and variance of the samples of a pixel, following Welford's
+/// algorithm for the luminance.
+#[derive(Copy, Clone, Default)]
+pub struct PixelStats {
+    count: usize,
+    sum: Color,
+    mean: f64,
+    squared_deviations: f64,
+}
+
+impl PixelStats {
+    pub fn add(&mut self, color: Color) {
+        self.count += 1;
+        self.sum += color;
+        let luminance = tonemap::luminance(color);
+        let delta = luminance - self.mean;
+        self.mean += delta / self.count as f64;
+        self.squared_deviations += delta * (luminance - self.mean);
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn mean(&self) -> Color {
+        self.sum / self.count.max(1) as f64
+    }
+
+    /// Unbiased estimate of the variance of the luminance of the samples.
+    pub fn variance(&self) -> f64 {
+        if self.count < 2 {
+            return 0.0;
+        }
+        self.squared_deviations / (self.count - 1) as f64
+    }
+
+    /// The state of the statistics: count, sum of the colors, mean and sum of
+    /// the squared deviations of the luminance.
+    pub(crate) fn to_raw(self) -> (usize, Color, f64, f64) {
+        (self.count, self.sum, self.mean, self.squared_deviations)
+    }
+
+    pub(crate) fn from_raw(count: usize, sum: Color, mean: f64, squared_deviations: f64) -> Self {
+        PixelStats {
+            count,
+            sum,
+            mean,
+            squared_deviations,
+        }
+    }
+
+    /// Standard error of the mean luminance, relative to it.
+    pub fn relative_error(&self) -> f64 {
+        if self.count < 2 {
+            return f64::INFINITY;
+        }
+        (self.variance() / self.count as f64).sqrt() / self.mean.max(LUMINANCE_FLOOR)
+    }
+}
@@ -0,0 +1,145 @@
+//! Reporting of the advancement of a render.
+
+use super::tile::Tile;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::io::Write;
+use std::sync::Mutex;
+
+/// Receiver of the events of a render, called from the threads rendering the
+/// tiles.
+pub trait ProgressSink: Sync {
+    /// A pass bringing the pixels to `samples` samples starts over `tiles`.
+    fn pass_started(&self, _samples: usize, _tiles: &[Tile]) {}
+
+    /// All the pixels of `tile` were rendered.
+    fn tile_finished(&self, _tile: &Tile) {}
+
+    /// The pass bringing the pixels to `samples` samples ended.
+    fn pass_finished(&self, _samples: usize) {}
+
+    /// The pass bringing the pixels to `samples` samples was cancelled before
+    /// the end.
+    fn pass_cancelled(&self, _samples: usize) {}
+}
+
+/// Ignore the events, for headless runs.
+pub struct Silent;
+
+impl ProgressSink for Silent {}
+
+/// Progress bar drawn on the terminal, one by pass.
+#[derive(Default)]
+pub struct Bar {
+    bar: Mutex<Option<ProgressBar>>,
+}
+
+impl ProgressSink for Bar {
+    fn pass_started(&self, samples: usize, tiles: &[Tile]) {
+        let pixels = tiles.iter().map(Tile::pixel_count).sum::<usize>();
+        let bar = ProgressBar::new(pixels as u64);
+        bar.set_prefix(format!("Render in progress ({} spp)", samples));
+        bar.set_style(
+            ProgressStyle::default_bar()
+                .template("{prefix:.white} {bar:40.cyan/blue} {percent}% [{eta_precise}]"),
+        );
+        *self.bar.lock().unwrap() = Some(bar);
+    }
+
+    fn tile_finished(&self, tile: &Tile) {
+        if let Some(bar) = &*self.bar.lock().unwrap() {
+            bar.inc(tile.pixel_count() as u64);
+        }
+    }
+
+    fn pass_finished(&self, _samples: usize) {
+        if let Some(bar) = self.bar.lock().unwrap().take() {
+            bar.finish();
+        }
+    }
+
+    fn pass_cancelled(&self, _samples: usize) {
+        if let Some(bar) = self.bar.lock().unwrap().take() {
+            bar.abandon();
+        }
+    }
+}
+
+/// One JSON object by line for each event, for other programs to follow the
+/// render:
+///
+/// ```text
+/// {"event":"pass_started","samples":16,"tiles":60}
+/// {"event":"tile_finished","samples":16,"x":96,"y":64,"width":32,"height":32,"done":1,"tiles":60}
+/// {"event":"pass_finished","samples":16}
+/// ```
+///
+/// A cancelled pass ends with `{"event":"pass_cancelled","samples":16}`
+/// instead.
+pub struct Json<W> {
+    state: Mutex<JsonState<W>>,
+}
+
+struct JsonState<W> {
+    writer: W,
+    samples: usize,
+    tiles: usize,
+    done: usize,
+}
+
+impl<W: Write + Send> Json<W> {
+    pub fn new(writer: W) -> Self {
+        Json {
+            state: Mutex::new(JsonState {
+                writer,
+                samples: 0,
+                tiles: 0,
+                done: 0,
+            }),
+        }
+    }
+}
+
+impl<W: Write> JsonState<W> {
+    fn emit(&mut self, line: String) {
+        // Progress is best effort, a closed pipe must not stop the render.
+        let _ = writeln!(self.writer, "{}", line).and_then(|()| self.writer.flush());
+    }
+}
+
+impl<W: Write + Send> ProgressSink for Json<W> {
+    fn pass_started(&self, samples: usize, tiles: &[Tile]) {
+        let mut state = self.state.lock().unwrap();
+        state.samples = samples;
+        state.tiles = tiles.len();
+        state.done = 0;
+        state.emit(format!(
+            r#"{{"event":"pass_started","samples":{},"tiles":{}}}"#,
+            samples,
+            tiles.len()
+        ));
+    }
+
+    fn tile_finished(&self, tile: &Tile) {
+        let mut state = self.state.lock().unwrap();
+        state.done += 1;
+        let line = format!(
+            r#"{{"event":"tile_finished","samples":{},"x":{},"y":{},"width":{},"height":{},"done":{},"tiles":{}}}"#,
+            state.samples, tile.x, tile.y, tile.width, tile.height, state.done, state.tiles
+        );
+        state.emit(line);
+    }
+
+    fn pass_finished(&self, samples: usize) {
+        self.state.lock().unwrap().emit(format!(
+            r#"{{"event":"pass_finished","samples":{}}}"#,
+            samples
+        ));
+    }
+
+    fn pass_cancelled(&self, samples: usize) {
+        self.state.lock().unwrap().emit(format!(
+            r#"{{"event":"pass_cancelled","samples":{}}}"#,
+            samples
+        ));
+    }
+}
@@ -0,0 +1,441 @@
+//! Rendering in successive passes, each one adding samples to the pixels
+//! accumulated so far, and checkpoints saving them to resume an interrupted
+//! render.
+
+use super::adaptive::{AdaptiveSampling, PixelStats};
+use super::RenderSettings;
+use crate::aov::{Aov, AovFilms, AovSamples};
+use crate::denoise::{Denoiser, Guides};
+use crate::film::Film;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::time::Duration;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Progressive {
+    /// Number of samples added to each pixel by a pass.
+    pub pass_samples: usize,
+    /// Wall-clock time after which no new pass is started.
+    pub time_budget: Option<Duration>,
+}
+
+impl Default for Progressive {
+    fn default() -> Self {
+        Progressive {
+            pass_samples: 16,
+            time_budget: None,
+        }
+    }
+}
+
+/// State of a pixel between passes.
+#[derive(Copy, Clone, Default)]
+pub(crate) struct PixelState {
+    pub(crate) stats: PixelStats,
+    pub(crate) aovs: AovSamples,
+    /// Whether adaptive sampling stopped sampling the pixel.
+    pub(crate) converged: bool,
+}
+
+/// The samples of an image being rendered, stored row by row starting from
+/// the top left corner.
+pub struct Accumulation {
+    width: usize,
+    height: usize,
+    /// Variables requested from the render.
+    aovs: Vec<Aov>,
+    /// Variables accumulated, including the guides of the denoiser.
+    rendered_aovs: Vec<Aov>,
+    /// Number of samples the pixels have been brought to, unless converged.
+    samples: usize,
+    pub(crate) pixels: Vec<PixelState>,
+}
+
+impl Accumulation {
+    pub fn new(settings: &RenderSettings, aovs: &[Aov]) -> Self {
+        let mut rendered_aovs = aovs.to_vec();
+        if settings.denoiser != Denoiser::Off {
+            rendered_aovs.extend(Denoiser::GUIDES);
+        }
+        Accumulation {
+            width: settings.image_width,
+            height: settings.image_height,
+            aovs: aovs.to_vec(),
+            rendered_aovs,
+            samples: 0,
+            pixels: vec![PixelState::default(); settings.image_width * settings.image_height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Number of samples of the pixels still being sampled.
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    pub(crate) fn set_samples(&mut self, samples: usize) {
+        self.samples = samples;
+    }
+
+    pub(crate) fn with_aovs(&self) -> bool {
+        !self.rendered_aovs.is_empty()
+    }
+
+    /// Whether adaptive sampling stopped sampling every pixel.
+    pub fn converged(&self) -> bool {
+        self.pixels.iter().all(|pixel| pixel.converged)
+    }
+
+    /// Mean of the samples of each pixel.
+    pub fn film(&self) -> Film {
+        let data = self
+            .pixels
+            .iter()
+            .map(|pixel| {
+                let color = pixel.stats.mean();
+                [color.x() as f32, color.y() as f32, color.z() as f32]
+            })
+            .collect();
+        Film::from_raw(self.width, self.height, data)
+    }
+
+    /// Number of samples taken by each pixel.
+    pub fn sample_counts(&self) -> Vec<usize> {
+        self.pixels
+            .iter()
+            .map(|pixel| pixel.stats.count())
+            .collect()
+    }
+
+    /// Whether each pixel has at least `samples` samples or was found
+    /// converged, the other ones being left behind by a cancelled pass.
+    pub fn completed(&self, samples: usize) -> Vec<bool> {
+        self.pixels
+            .iter()
+            .map(|pixel| pixel.converged || pixel.stats.count() >= samples)
+            .collect()
+    }
+
+    /// The image and the requested variables, denoised as configured.
+    pub fn finish(&self, settings: &RenderSettings) -> (Film, AovFilms) {
+        let mut film = self.film();
+        let aov_pixels: Vec<_> = self
+            .pixels
+            .iter()
+            .map(|pixel| pixel.aovs.average(pixel.stats.count()))
+            .collect();
+        let mut aov_films =
+            AovFilms::new(self.width, self.height, &self.rendered_aovs, &aov_pixels);
+
+        if settings.denoiser != Denoiser::Off {
+            let guide = |aov| aov_films.get(aov).expect("guides are rendered");
+            let guides = Guides {
+                albedo: guide(Aov::Albedo),
+                normal: guide(Aov::Normal),
+                depth: guide(Aov::Depth),
+            };
+            film = settings.denoiser.apply(&film, &guides);
+        }
+        aov_films.retain(&self.aovs);
+        (film, aov_films)
+    }
+
+    /// Write the samples accumulated so far, replacing the file only once it
+    /// is complete.
+    ///
+    /// `scene` identifies the rendered scene, for instance by its name or a
+    /// hash of its description, so that the checkpoint cannot be resumed
+    /// with another one.
+    ///
+    /// The output variables are saved along with the samples when they are
+    /// rendered. Materials are identified by their order of appearance in the
+    /// image, a material first seen in a pixel only after a resume getting
+    /// another identifier.
+    pub fn save_checkpoint<P: AsRef<Path>>(
+        &self,
+        path: P,
+        settings: &RenderSettings,
+        scene: &str,
+    ) -> io::Result<()> {
+        let path = path.as_ref();
+        let mut partial = path.as_os_str().to_os_string();
+        partial.push(".partial");
+
+        let mut writer = BufWriter::new(File::create(&partial)?);
+        writer.write_all(MAGIC)?;
+        write_u64(&mut writer, self.width as u64)?;
+        write_u64(&mut writer, self.height as u64)?;
+        write_u64(&mut writer, settings.seed)?;
+        write_string(&mut writer, settings.sampler.name())?;
+        write_string(&mut writer, scene)?;
+        write_u64(&mut writer, settings.sample_per_pixel as u64)?;
+        write_u64(&mut writer, settings.max_depth as u64)?;
+        writer.write_all(&[settings.adaptive.is_some() as u8])?;
+        if let Some(adaptive) = settings.adaptive {
+            write_f64s(&mut writer, &[adaptive.threshold])?;
+            write_u64(&mut writer, adaptive.max_samples as u64)?;
+        }
+        write_u64(&mut writer, self.samples as u64)?;
+        let with_aovs = self.with_aovs();
+        writer.write_all(&[with_aovs as u8])?;
+        // Material identifiers are addresses, only meaningful to this process.
+        let mut materials = HashMap::new();
+        for pixel in &self.pixels {
+            let (count, sum, mean, squared_deviations) = pixel.stats.to_raw();
+            write_u64(&mut writer, count as u64)?;
+            write_f64s(
+                &mut writer,
+                &[sum.x(), sum.y(), sum.z(), mean, squared_deviations],
+            )?;
+            writer.write_all(&[pixel.converged as u8])?;
+
+            if with_aovs {
+                let (count, hits, sums, material) = pixel.aovs.to_raw();
+                write_u64(&mut writer, count as u64)?;
+                write_u64(&mut writer, hits as u64)?;
+                write_f64s(&mut writer, &sums)?;
+                let material = material.map_or(0, |material| {
+                    let next = materials.len() + 1;
+                    *materials.entry(material).or_insert(next)
+                });
+                write_u64(&mut writer, material as u64)?;
+            }
+        }
+        writer
+            .into_inner()
+            .map_err(|err| err.into_error())?
+            .sync_all()?;
+        fs::rename(&partial, path)
+    }
+
+    /// Read a checkpoint written by `save_checkpoint` for a render of the
+    /// same scene with the same settings.
+    ///
+    /// Only the settings scheduling the work, the progressive passes and the
+    /// tiles, may differ from the ones of the checkpoint.
+    pub fn load_checkpoint<P: AsRef<Path>>(
+        path: P,
+        settings: &RenderSettings,
+        scene: &str,
+        aovs: &[Aov],
+    ) -> Result<Self, CheckpointError> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut magic = [0; MAGIC.len()];
+        reader.read_exact(&mut magic)?;
+        if magic[..6] == MAGIC[..6] && magic != *MAGIC {
+            return Err(CheckpointError::Format(format!(
+                "unsupported version {}",
+                String::from_utf8_lossy(&magic[6..])
+            )));
+        }
+        if magic != *MAGIC {
+            return Err(CheckpointError::Format("not a checkpoint".to_string()));
+        }
+
+        let mut accumulation = Accumulation::new(settings, aovs);
+        let size = (read_u64(&mut reader)?, read_u64(&mut reader)?);
+        if size != (accumulation.width as u64, accumulation.height as u64) {
+            return Err(CheckpointError::Mismatch(format!(
+                "image size {}x{} instead of {}x{}",
+                size.0, size.1, accumulation.width, accumulation.height
+            )));
+        }
+        let seed = read_u64(&mut reader)?;
+        if seed != settings.seed {
+            return Err(CheckpointError::Mismatch(format!(
+                "seed {} instead of {}",
+                seed, settings.seed
+            )));
+        }
+        let sampler = read_string(&mut reader, 64, "sampler name")?;
+        if sampler != settings.sampler.name() {
+            return Err(CheckpointError::Mismatch(format!(
+                "sampler {} instead of {}",
+                sampler, settings.sampler
+            )));
+        }
+        let saved_scene = read_string(&mut reader, 4096, "scene")?;
+        if saved_scene != scene {
+            return Err(CheckpointError::Mismatch(format!(
+                "scene `{}` instead of `{}`",
+                saved_scene, scene
+            )));
+        }
+        let sample_per_pixel = read_u64(&mut reader)?;
+        if sample_per_pixel != settings.sample_per_pixel as u64 {
+            return Err(CheckpointError::Mismatch(format!(
+                "{} samples per pixel instead of {}",
+                sample_per_pixel, settings.sample_per_pixel
+            )));
+        }
+        let max_depth = read_u64(&mut reader)?;
+        if max_depth != settings.max_depth as u64 {
+            return Err(CheckpointError::Mismatch(format!(
+                "maximum depth {} instead of {}",
+                max_depth, settings.max_depth
+            )));
+        }
+        let adaptive = match read_u8(&mut reader)? {
+            0 => None,
+            _ => {
+                let [threshold] = read_f64s(&mut reader)?;
+                Some(AdaptiveSampling::new(
+                    threshold,
+                    read_u64(&mut reader)? as usize,
+                ))
+            }
+        };
+        if adaptive != settings.adaptive {
+            return Err(CheckpointError::Mismatch(format!(
+                "{} instead of {}",
+                describe_adaptive(adaptive),
+                describe_adaptive(settings.adaptive)
+            )));
+        }
+        accumulation.samples = read_u64(&mut reader)? as usize;
+        let with_aovs = read_u8(&mut reader)? != 0;
+        if accumulation.with_aovs() && !with_aovs && accumulation.samples > 0 {
+            return Err(CheckpointError::Mismatch(
+                "saved without the output variables the denoiser and `--aov` need".to_string(),
+            ));
+        }
+
+        for pixel in &mut accumulation.pixels {
+            let count = read_u64(&mut reader)? as usize;
+            let [r, g, b, mean, squared_deviations] = read_f64s(&mut reader)?;
+            pixel.stats = PixelStats::from_raw(count, color!(r, g, b), mean, squared_deviations);
+            pixel.converged = read_u8(&mut reader)? != 0;
+
+            if with_aovs {
+                let count = read_u64(&mut reader)? as usize;
+                let hits = read_u64(&mut reader)? as usize;
+                let sums = read_f64s(&mut reader)?;
+                let material = match read_u64(&mut reader)? {
+                    0 => None,
+                    index => Some(index as usize),
+                };
+                pixel.aovs = AovSamples::from_raw(count, hits, sums, material);
+            }
+        }
+        if reader.read(&mut [0])? != 0 {
+            return Err(CheckpointError::Format("trailing data".to_string()));
+        }
+        Ok(accumulation)
+    }
+}
+
+/// Signature and version of the checkpoint files, version 2 adding the
+/// output variables and version 3 the scene and the sampling settings.
+const MAGIC: &[u8; 8] = b"RTCKPT03";
+
+fn describe_adaptive(adaptive: Option<AdaptiveSampling>) -> String {
+    match adaptive {
+        Some(adaptive) => format!(
+            "adaptive sampling to a threshold of {} with at most {} samples",
+            adaptive.threshold, adaptive.max_samples
+        ),
+        None => "no adaptive sampling".to_string(),
+    }
+}
+
+fn write_u64(writer: &mut impl Write, value: u64) -> io::Result<()> {
+    writer.write_all(&value.to_le_bytes())
+}
+
+fn write_f64s(writer: &mut impl Write, values: &[f64]) -> io::Result<()> {
+    for value in values {
+        writer.write_all(&value.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+fn write_string(writer: &mut impl Write, value: &str) -> io::Result<()> {
+    write_u64(writer, value.len() as u64)?;
+    writer.write_all(value.as_bytes())
+}
+
+fn read_u8(reader: &mut impl Read) -> io::Result<u8> {
+    let mut byte = [0];
+    reader.read_exact(&mut byte)?;
+    Ok(byte[0])
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+/// Read a string written by `write_string`, at most `max_length` bytes long.
+fn read_string(
+    reader: &mut impl Read,
+    max_length: u64,
+    what: &str,
+) -> Result<String, CheckpointError> {
+    let length = read_u64(reader)?;
+    if length > max_length {
+        return Err(CheckpointError::Format(format!("invalid {}", what)));
+    }
+    let mut bytes = vec![0; length as usize];
+    reader.read_exact(&mut bytes)?;
+    String::from_utf8(bytes).map_err(|_| CheckpointError::Format(format!("invalid {}", what)))
+}
+
+fn read_f64s<const N: usize>(reader: &mut impl Read) -> io::Result<[f64; N]> {
+    let mut values = [0.0; N];
+    for value in &mut values {
+        let mut bytes = [0; 8];
+        reader.read_exact(&mut bytes)?;
+        *value = f64::from_le_bytes(bytes);
+    }
+    Ok(values)
+}
+
+#[derive(Debug)]
+pub enum CheckpointError {
+    Io(io::Error),
+    Format(String),
+    /// The checkpoint was saved by a render with other settings.
+    Mismatch(String),
+}
+
+impl fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CheckpointError::Io(err) if err.kind() == io::ErrorKind::UnexpectedEof => {
+                write!(f, "invalid checkpoint: truncated file")
+            }
+            CheckpointError::Io(err) => write!(f, "{}", err),
+            CheckpointError::Format(message) => write!(f, "invalid checkpoint: {}", message),
+            CheckpointError::Mismatch(message) => {
+                write!(f, "checkpoint of another render: {}", message)
+            }
+        }
+    }
+}
+
+impl Error for CheckpointError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CheckpointError::Io(err) => Some(err),
+            CheckpointError::Format(_) | CheckpointError::Mismatch(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for CheckpointError {
+    fn from(err: io::Error) -> Self {
+        CheckpointError::Io(err)
+    }
+}
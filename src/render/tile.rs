@@ -0,0 +1,156 @@
+//! Division of the image into tiles rendered as units of work, and the order
+//! in which they are handed out.
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// A rectangle of pixels, in image coordinates starting from the top left
+/// corner.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Tile {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Tile {
+    pub fn pixel_count(&self) -> usize {
+        self.width * self.height
+    }
+
+    /// Image coordinates of the pixels, row by row.
+    pub fn pixels(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (self.y..self.y + self.height)
+            .flat_map(move |y| (self.x..self.x + self.width).map(move |x| (x, y)))
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum TileOrder {
+    /// Row by row from the top.
+    Scanline,
+    /// Outwards from the center of the image, where the subject usually is.
+    #[default]
+    Spiral,
+    /// Along a Hilbert curve, keeping consecutive tiles next to each other.
+    Hilbert,
+}
+
+impl TileOrder {
+    pub const ALL: [TileOrder; 3] = [TileOrder::Scanline, TileOrder::Spiral, TileOrder::Hilbert];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TileOrder::Scanline => "scanline",
+            TileOrder::Spiral => "spiral",
+            TileOrder::Hilbert => "hilbert",
+        }
+    }
+}
+
+impl fmt::Display for TileOrder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Debug)]
+pub struct UnknownTileOrderError(String);
+
+impl fmt::Display for UnknownTileOrderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names: Vec<_> = TileOrder::ALL.iter().map(TileOrder::name).collect();
+        write!(
+            f,
+            "unknown tile order `{}`, expected one of {}",
+            self.0,
+            names.join(", ")
+        )
+    }
+}
+
+impl Error for UnknownTileOrderError {}
+
+impl FromStr for TileOrder {
+    type Err = UnknownTileOrderError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        TileOrder::ALL
+            .into_iter()
+            .find(|order| order.name() == s)
+            .ok_or_else(|| UnknownTileOrderError(s.to_string()))
+    }
+}
+
+/// Cover an image with tiles of `size` pixels aside, smaller along the right
+/// and bottom edges, sorted in the given order.
+///
+/// # Panics
+///
+/// Panics if `size` is 0.
+pub fn tiles(width: usize, height: usize, size: usize, order: TileOrder) -> Vec<Tile> {
+    assert!(size > 0, "empty tiles");
+    let columns = width.div_ceil(size);
+    let rows = height.div_ceil(size);
+
+    let mut grid: Vec<(usize, usize)> = (0..rows)
+        .flat_map(|row| (0..columns).map(move |column| (column, row)))
+        .collect();
+    match order {
+        TileOrder::Scanline => {}
+        TileOrder::Spiral => {
+            // Rings of tiles around the center, each one walked around by angle.
+            let center = (columns as f64 / 2.0, rows as f64 / 2.0);
+            let key = |&(column, row): &(usize, usize)| {
+                let dx = column as f64 + 0.5 - center.0;
+                let dy = row as f64 + 0.5 - center.1;
+                (dx.abs().max(dy.abs()).floor() as usize, dy.atan2(dx))
+            };
+            grid.sort_by(|a, b| {
+                let (ring_a, angle_a) = key(a);
+                let (ring_b, angle_b) = key(b);
+                ring_a.cmp(&ring_b).then(angle_a.total_cmp(&angle_b))
+            });
+        }
+        TileOrder::Hilbert => {
+            let side = columns.max(rows).next_power_of_two();
+            grid.sort_by_key(|&(column, row)| hilbert_index(side, column, row));
+        }
+    }
+
+    grid.into_iter()
+        .map(|(column, row)| {
+            let (x, y) = (column * size, row * size);
+            Tile {
+                x,
+                y,
+                width: size.min(width - x),
+                height: size.min(height - y),
+            }
+        })
+        .collect()
+}
+
+/// Distance along the Hilbert curve filling a square of `side` cells, a power
+/// of two, to the cell at `(x, y)`.
+fn hilbert_index(side: usize, mut x: usize, mut y: usize) -> usize {
+    let mut index = 0;
+    let mut s = side / 2;
+    while s > 0 {
+        let rx = (x & s > 0) as usize;
+        let ry = (y & s > 0) as usize;
+        index += s * s * ((3 * rx) ^ ry);
+        // Rotate the quadrant so that the curve is continuous.
+        if ry == 0 {
+            if rx == 1 {
+                x = s - 1 - (x & (s - 1));
+                y = s - 1 - (y & (s - 1));
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        s /= 2;
+    }
+    index
+}
@@ -0,0 +1,156 @@
+//! Sources of the values used to jitter pixels, sample the lens and the
+//! shutter, and scatter rays off surfaces.
+//!
+//! A sampler is positioned on a sample of a pixel, then hands out the values
+//! of that sample one dimension after another. Low-discrepancy samplers spread
+//! the samples of a pixel evenly in every dimension, which converges with far
+//! fewer samples than independent random values.
+
+use crate::vec3::Vec3;
+use std::error::Error;
+use std::f64::consts::PI;
+use std::fmt;
+use std::str::FromStr;
+
+pub mod blue_noise;
+pub mod halton;
+pub mod independent;
+pub mod sobol;
+pub mod stratified;
+
+pub trait Sampler {
+    /// Move to sample `index` of the pixel at column `x` and row `y`, restarting
+    /// from its first dimension.
+    fn start_pixel_sample(&mut self, x: usize, y: usize, index: usize);
+
+    /// Value of the next dimension, in `[0, 1)`.
+    fn get_1d(&mut self) -> f64;
+
+    /// Values of the next two dimensions, in `[0, 1)`.
+    fn get_2d(&mut self) -> (f64, f64);
+}
+
+/// The samplers selectable from the render settings.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum SamplerKind {
+    /// Independent uniform random values.
+    Uniform,
+    /// One jittered sample in each stratum of a grid.
+    Stratified,
+    /// Randomly shifted Halton sequence.
+    Halton,
+    /// Owen scrambled Sobol sequence.
+    #[default]
+    Sobol,
+    /// Sequence offset by a blue noise mask, spreading the error between
+    /// neighboring pixels.
+    BlueNoise,
+}
+
+impl SamplerKind {
+    pub const ALL: [SamplerKind; 5] = [
+        SamplerKind::Uniform,
+        SamplerKind::Stratified,
+        SamplerKind::Halton,
+        SamplerKind::Sobol,
+        SamplerKind::BlueNoise,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SamplerKind::Uniform => "uniform",
+            SamplerKind::Stratified => "stratified",
+            SamplerKind::Halton => "halton",
+            SamplerKind::Sobol => "sobol",
+            SamplerKind::BlueNoise => "blue-noise",
+        }
+    }
+
+    /// Create a sampler for images with `samples_per_pixel` samples by pixel.
+    pub fn create(&self, samples_per_pixel: usize, seed: u64) -> Box<dyn Sampler> {
+        match self {
+            SamplerKind::Uniform => Box::new(independent::Independent::new(seed)),
+            SamplerKind::Stratified => {
+                Box::new(stratified::Stratified::new(samples_per_pixel, seed))
+            }
+            SamplerKind::Halton => Box::new(halton::Halton::new(seed)),
+            SamplerKind::Sobol => Box::new(sobol::Sobol::new(seed)),
+            SamplerKind::BlueNoise => Box::new(blue_noise::BlueNoise::new(seed)),
+        }
+    }
+}
+
+impl fmt::Display for SamplerKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Debug)]
+pub struct UnknownSamplerError(String);
+
+impl fmt::Display for UnknownSamplerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names: Vec<_> = SamplerKind::ALL.iter().map(SamplerKind::name).collect();
+        write!(
+            f,
+            "unknown sampler `{}`, expected one of {}",
+            self.0,
+            names.join(", ")
+        )
+    }
+}
+
+impl Error for UnknownSamplerError {}
+
+impl FromStr for SamplerKind {
+    type Err = UnknownSamplerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SamplerKind::ALL
+            .into_iter()
+            .find(|kind| kind.name() == s)
+            .ok_or_else(|| UnknownSamplerError(s.to_string()))
+    }
+}
+
+/// Largest `f64` below 1, keeping the samples out of the closed end.
+const ONE_MINUS_EPSILON: f64 = 1.0 - f64::EPSILON / 2.0;
+
+/// Map 32 random bits to `[0, 1)`.
+fn to_unit(bits: u32) -> f64 {
+    bits as f64 / (1u64 << 32) as f64
+}
+
+/// Identify a pixel in the hashes deriving the streams of its samples.
+fn pixel_key(x: usize, y: usize) -> u64 {
+    (y as u64) << 32 | x as u64
+}
+
+/// Point of the unit disk in the `z = 0` plane, using the concentric mapping
+/// which keeps the stratification of the samples.
+pub fn sample_disk((u, v): (f64, f64)) -> Vec3 {
+    let (x, y) = (2.0 * u - 1.0, 2.0 * v - 1.0);
+    if x == 0.0 && y == 0.0 {
+        return Vec3::default();
+    }
+    let (radius, theta) = if x.abs() > y.abs() {
+        (x, PI / 4.0 * (y / x))
+    } else {
+        (y, PI / 2.0 - PI / 4.0 * (x / y))
+    };
+    vec3!(radius * theta.cos(), radius * theta.sin(), 0.0)
+}
+
+/// Uniformly distributed point on the unit sphere.
+pub fn sample_sphere((u, v): (f64, f64)) -> Vec3 {
+    let z = 1.0 - 2.0 * u;
+    let radius = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * PI * v;
+    vec3!(radius * phi.cos(), radius * phi.sin(), z)
+}
+
+/// Uniformly distributed point inside the unit sphere.
+pub fn sample_ball(direction: (f64, f64), radius: f64) -> Vec3 {
+    radius.cbrt() * sample_sphere(direction)
+}
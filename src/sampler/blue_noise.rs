@@ -0,0 +1,114 @@
+use super::{pixel_key, Sampler};
+use crate::utils::stream_seed;
+use std::sync::OnceLock;
+
+/// Side of the tiled blue noise mask.
+const MASK_SIZE: usize = 64;
+
+/// Additive recurrences with the best known spacing in one and two dimensions,
+/// based on the golden ratio and its 2D generalization.
+const R1: f64 = 0.618_033_988_749_894_9;
+const R2: (f64, f64) = (0.754_877_666_246_692_7, 0.569_840_290_998_053_2);
+
+/// Samples offset by a blue noise mask tiled over the image, each dimension
+/// reading it at its own random shift, then advanced from one sample to the
+/// next with an additive recurrence. The error of neighboring pixels is then
+/// uncorrelated, appearing as high frequency noise instead of clumps.
+pub struct BlueNoise {
+    seed: u64,
+    x: usize,
+    y: usize,
+    index: usize,
+    dimension: u64,
+}
+
+impl BlueNoise {
+    pub fn new(seed: u64) -> Self {
+        BlueNoise {
+            seed,
+            x: 0,
+            y: 0,
+            index: 0,
+            dimension: 0,
+        }
+    }
+
+    /// Value of the mask at the current pixel for the next dimension.
+    fn offset(&mut self) -> f64 {
+        let hash = stream_seed(self.seed, 0, self.dimension);
+        self.dimension += 1;
+        let x = (self.x + hash as usize) % MASK_SIZE;
+        let y = (self.y + (hash >> 32) as usize) % MASK_SIZE;
+        let rank = mask()[y * MASK_SIZE + x];
+        (rank as f64 + 0.5) / (MASK_SIZE * MASK_SIZE) as f64
+    }
+}
+
+impl Sampler for BlueNoise {
+    fn start_pixel_sample(&mut self, x: usize, y: usize, index: usize) {
+        // Shift the tiles of the mask by pixel blocks so that they do not
+        // repeat exactly across the image.
+        let block = stream_seed(self.seed, pixel_key(x / MASK_SIZE, y / MASK_SIZE), 0);
+        self.x = x + block as usize % MASK_SIZE;
+        self.y = y + (block >> 32) as usize % MASK_SIZE;
+        self.index = index;
+        self.dimension = 0;
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        (self.offset() + self.index as f64 * R1).fract()
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let (u, v) = (self.offset(), self.offset());
+        let index = self.index as f64;
+        ((u + index * R2.0).fract(), (v + index * R2.1).fract())
+    }
+}
+
+/// Ranks of the pixels of the mask, generated once.
+fn mask() -> &'static [u16] {
+    static MASK: OnceLock<Vec<u16>> = OnceLock::new();
+    MASK.get_or_init(void_and_cluster)
+}
+
+/// Rank the pixels by repeatedly picking the one farthest from the already
+/// ranked ones, measured by a Gaussian energy on the torus (Ulichney, "The
+/// void-and-cluster method for dither array generation").
+fn void_and_cluster() -> Vec<u16> {
+    const SIGMA: f64 = 1.5;
+    let count = MASK_SIZE * MASK_SIZE;
+
+    let kernel: Vec<f64> = (0..count)
+        .map(|k| {
+            let (dx, dy) = (k % MASK_SIZE, k / MASK_SIZE);
+            let dx = dx.min(MASK_SIZE - dx) as f64;
+            let dy = dy.min(MASK_SIZE - dy) as f64;
+            (-(dx * dx + dy * dy) / (2.0 * SIGMA * SIGMA)).exp()
+        })
+        .collect();
+
+    // A tiny deterministic perturbation breaks the ties that would otherwise
+    // grow a regular lattice.
+    let mut energy: Vec<f64> = (0..count)
+        .map(|k| 1e-9 * (stream_seed(0, k as u64, 0) >> 11) as f64 / (1u64 << 53) as f64)
+        .collect();
+    let mut ranks = vec![None; count];
+
+    for rank in 0..count {
+        let void = (0..count)
+            .filter(|&k| ranks[k].is_none())
+            .min_by(|&a, &b| energy[a].total_cmp(&energy[b]))
+            .unwrap();
+        ranks[void] = Some(rank as u16);
+
+        let (vx, vy) = (void % MASK_SIZE, void / MASK_SIZE);
+        for (k, value) in energy.iter_mut().enumerate() {
+            let dx = (k % MASK_SIZE + MASK_SIZE - vx) % MASK_SIZE;
+            let dy = (k / MASK_SIZE + MASK_SIZE - vy) % MASK_SIZE;
+            *value += kernel[dy * MASK_SIZE + dx];
+        }
+    }
+
+    ranks.into_iter().map(Option::unwrap).collect()
+}
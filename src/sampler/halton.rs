@@ -0,0 +1,75 @@
+use super::{pixel_key, to_unit, Sampler, ONE_MINUS_EPSILON};
+use crate::utils::stream_seed;
+
+/// Bases of the dimensions of the sequence.
+const PRIMES: [u64; 64] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151, 157, 163, 167, 173, 179, 181, 191, 193,
+    197, 199, 211, 223, 227, 229, 233, 239, 241, 251, 257, 263, 269, 271, 277, 281, 283, 293, 307,
+    311,
+];
+
+/// Halton sequence with a random shift by pixel and dimension (Cranley-Patterson
+/// rotation) so that neighboring pixels do not share the same points.
+///
+/// The dimensions past the last base fall back to independent random values.
+pub struct Halton {
+    seed: u64,
+    pixel: u64,
+    index: u64,
+    dimension: u64,
+}
+
+impl Halton {
+    pub fn new(seed: u64) -> Self {
+        Halton {
+            seed,
+            pixel: 0,
+            index: 0,
+            dimension: 0,
+        }
+    }
+
+    fn sample(&mut self) -> f64 {
+        let hash = stream_seed(self.seed, self.pixel, self.dimension);
+        let value = match PRIMES.get(self.dimension as usize) {
+            Some(&base) => {
+                let shifted = radical_inverse(base, self.index) + to_unit(hash as u32);
+                shifted.fract().min(ONE_MINUS_EPSILON)
+            }
+            None => to_unit(stream_seed(hash, self.index, 0) as u32),
+        };
+        self.dimension += 1;
+        value
+    }
+}
+
+impl Sampler for Halton {
+    fn start_pixel_sample(&mut self, x: usize, y: usize, index: usize) {
+        self.pixel = pixel_key(x, y);
+        self.index = index as u64;
+        self.dimension = 0;
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        self.sample()
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        (self.sample(), self.sample())
+    }
+}
+
+/// Mirror the digits of `index` written in `base` around the radix point.
+fn radical_inverse(base: u64, mut index: u64) -> f64 {
+    let inverse_base = 1.0 / base as f64;
+    let mut reversed = 0;
+    let mut scale = 1.0;
+    while index > 0 {
+        let next = index / base;
+        reversed = reversed * base + (index - next * base);
+        scale *= inverse_base;
+        index = next;
+    }
+    (reversed as f64 * scale).min(ONE_MINUS_EPSILON)
+}
@@ -0,0 +1,34 @@
+use super::{pixel_key, Sampler};
+use crate::utils::stream_seed;
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+
+/// Independent uniform random values, each sample of each pixel drawing from
+/// its own stream.
+pub struct Independent {
+    seed: u64,
+    rng: SmallRng,
+}
+
+impl Independent {
+    pub fn new(seed: u64) -> Self {
+        Independent {
+            seed,
+            rng: SmallRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Sampler for Independent {
+    fn start_pixel_sample(&mut self, x: usize, y: usize, index: usize) {
+        self.rng = SmallRng::seed_from_u64(stream_seed(self.seed, pixel_key(x, y), index as u64));
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        self.rng.gen()
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        (self.rng.gen(), self.rng.gen())
+    }
+}
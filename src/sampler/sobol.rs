@@ -0,0 +1,101 @@
+use super::{pixel_key, to_unit, Sampler};
+use crate::utils::stream_seed;
+
+/// Owen scrambled Sobol points (Burley, "Practical Hash-based Owen
+/// Scrambling").
+///
+/// Every pair of dimensions uses the first two dimensions of the Sobol
+/// sequence with its own scrambling and its own shuffling of the sample
+/// indices, which keeps each pair well distributed without needing the
+/// direction numbers of higher dimensions.
+pub struct Sobol {
+    seed: u64,
+    pixel: u64,
+    index: u32,
+    dimension: u64,
+}
+
+impl Sobol {
+    pub fn new(seed: u64) -> Self {
+        Sobol {
+            seed,
+            pixel: 0,
+            index: 0,
+            dimension: 0,
+        }
+    }
+
+    /// Hash of the next dimension, advancing past `count` dimensions.
+    fn next_hash(&mut self, count: u64) -> u64 {
+        let hash = stream_seed(self.seed, self.pixel, self.dimension);
+        self.dimension += count;
+        hash
+    }
+}
+
+impl Sampler for Sobol {
+    fn start_pixel_sample(&mut self, x: usize, y: usize, index: usize) {
+        self.pixel = pixel_key(x, y);
+        self.index = index as u32;
+        self.dimension = 0;
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let hash = self.next_hash(1);
+        let index = nested_uniform_scramble(self.index, hash as u32);
+        to_unit(nested_uniform_scramble(
+            sobol_first(index),
+            (hash >> 32) as u32,
+        ))
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let hash = self.next_hash(2);
+        let index = nested_uniform_scramble(self.index, hash as u32);
+        let second_hash = stream_seed(hash, 0, 0);
+        (
+            to_unit(nested_uniform_scramble(
+                sobol_first(index),
+                (hash >> 32) as u32,
+            )),
+            to_unit(nested_uniform_scramble(
+                sobol_second(index),
+                second_hash as u32,
+            )),
+        )
+    }
+}
+
+/// First dimension of the Sobol sequence, the van der Corput sequence.
+fn sobol_first(index: u32) -> u32 {
+    index.reverse_bits()
+}
+
+/// Second dimension of the Sobol sequence, generated by the polynomial `x + 1`.
+fn sobol_second(mut index: u32) -> u32 {
+    let mut result = 0;
+    let mut direction = 1 << 31;
+    while index != 0 {
+        if index & 1 != 0 {
+            result ^= direction;
+        }
+        index >>= 1;
+        direction ^= direction >> 1;
+    }
+    result
+}
+
+/// Random permutation of the binary digits where each digit only depends on
+/// the digits above it, as an Owen scrambling does.
+fn nested_uniform_scramble(value: u32, seed: u32) -> u32 {
+    laine_karras_permutation(value.reverse_bits(), seed).reverse_bits()
+}
+
+fn laine_karras_permutation(mut x: u32, seed: u32) -> u32 {
+    x ^= x.wrapping_mul(0x3d20adea);
+    x = x.wrapping_add(seed);
+    x = x.wrapping_mul((seed >> 16) | 1);
+    x ^= x.wrapping_mul(0x05526c56);
+    x ^= x.wrapping_mul(0x53a22864);
+    x
+}
@@ -0,0 +1,105 @@
+use super::{pixel_key, Sampler};
+use crate::utils::stream_seed;
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+
+/// Jittered sampling: each dimension is split in as many strata as there are
+/// samples by pixel, 2D ones in a grid, and every sample of a pixel falls in a
+/// different stratum chosen by a per pixel and per dimension permutation.
+pub struct Stratified {
+    samples_per_pixel: usize,
+    /// Grid used for the 2D samples, with at least one cell per sample.
+    columns: usize,
+    rows: usize,
+    seed: u64,
+    pixel: u64,
+    index: usize,
+    dimension: u64,
+    rng: SmallRng,
+}
+
+impl Stratified {
+    pub fn new(samples_per_pixel: usize, seed: u64) -> Self {
+        let samples_per_pixel = samples_per_pixel.max(1);
+        let columns = (samples_per_pixel as f64).sqrt().ceil() as usize;
+        let rows = samples_per_pixel.div_ceil(columns);
+        Stratified {
+            samples_per_pixel,
+            columns,
+            rows,
+            seed,
+            pixel: 0,
+            index: 0,
+            dimension: 0,
+            rng: SmallRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Stratum of the current sample among `count` ones in the next
+    /// dimension.
+    fn stratum(&mut self, count: usize) -> usize {
+        let hash = stream_seed(self.seed, self.pixel, self.dimension) as u32;
+        permutation_element((self.index % count) as u32, count as u32, hash) as usize
+    }
+}
+
+impl Sampler for Stratified {
+    fn start_pixel_sample(&mut self, x: usize, y: usize, index: usize) {
+        self.pixel = pixel_key(x, y);
+        self.index = index;
+        self.dimension = 0;
+        self.rng = SmallRng::seed_from_u64(stream_seed(self.seed, self.pixel, index as u64));
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let stratum = self.stratum(self.samples_per_pixel);
+        self.dimension += 1;
+        (stratum as f64 + self.rng.gen::<f64>()) / self.samples_per_pixel as f64
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let stratum = self.stratum(self.columns * self.rows);
+        self.dimension += 2;
+        let (column, row) = (stratum % self.columns, stratum / self.columns);
+        (
+            (column as f64 + self.rng.gen::<f64>()) / self.columns as f64,
+            (row as f64 + self.rng.gen::<f64>()) / self.rows as f64,
+        )
+    }
+}
+
+/// Element `i` of a random permutation of `0..length` selected by `seed`,
+/// without storing the permutation (Kensler, "Correlated Multi-Jittered
+/// Sampling").
+fn permutation_element(mut i: u32, length: u32, seed: u32) -> u32 {
+    let mut w = length - 1;
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+    loop {
+        i ^= seed;
+        i = i.wrapping_mul(0xe170893d);
+        i ^= seed >> 16;
+        i ^= (i & w) >> 4;
+        i ^= seed >> 8;
+        i = i.wrapping_mul(0x0929eb3f);
+        i ^= seed >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | seed >> 27);
+        i = i.wrapping_mul(0x6935fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dcb303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e501cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860a3df);
+        i &= w;
+        i ^= i >> 5;
+        if i < length {
+            break;
+        }
+    }
+    ((i as u64 + seed as u64) % length as u64) as u32
+}
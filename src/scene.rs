@@ -1,14 +1,96 @@
+use crate::camera;
+use crate::environment::Environment;
+use crate::geometry::quad::{self, Quad};
 use crate::geometry::sphere::Sphere;
-use crate::hit::{Hit, Hittable};
 use crate::material::dielectric::Dielectric;
+use crate::material::diffuse_light::DiffuseLight;
 use crate::material::lambertian::Lambertian;
 use crate::material::metal::Metal;
 use crate::texture::checker_texture::CheckerTexture;
-use crate::utils::random;
-use crate::{Color, HittableList};
+use crate::utils::random_from;
+use crate::{Color, HittableList, Instance, Transform};
+use rand::rngs::SmallRng;
+use rand::SeedableRng;
 use std::sync::Arc;
 
-pub fn random_scene() -> HittableList {
+pub mod file;
+
+/// A named scene generator selectable from the command line.
+pub struct SceneEntry {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Build the objects, the seed driving the random ones.
+    pub build: fn(u64) -> HittableList,
+    pub camera: fn() -> camera::Builder,
+    pub environment: fn() -> Environment,
+}
+
+pub const SCENES: &[SceneEntry] = &[
+    SceneEntry {
+        name: "random_scene",
+        description: "Random small spheres with motion blur on a checkered ground",
+        build: random_scene,
+        camera: default_camera,
+        environment: Environment::default,
+    },
+    SceneEntry {
+        name: "final_first",
+        description: "Final scene of the first book: random static spheres",
+        build: final_first,
+        camera: default_camera,
+        environment: Environment::default,
+    },
+    SceneEntry {
+        name: "two_sphere",
+        description: "Two checkered spheres",
+        build: |_| two_sphere(),
+        camera: default_camera,
+        environment: Environment::default,
+    },
+    SceneEntry {
+        name: "quads",
+        description: "Five colored quads facing the camera",
+        build: |_| quads(),
+        camera: quads_camera,
+        environment: Environment::default,
+    },
+    SceneEntry {
+        name: "simple_light",
+        description: "Two spheres lit by a rectangle and a spherical light",
+        build: |_| simple_light(),
+        camera: simple_light_camera,
+        environment: dark_environment,
+    },
+    SceneEntry {
+        name: "cornell_box",
+        description: "Cornell box lit by a ceiling light",
+        build: |_| cornell_box(),
+        camera: cornell_box_camera,
+        environment: dark_environment,
+    },
+];
+
+pub fn find(name: &str) -> Option<&'static SceneEntry> {
+    SCENES.iter().find(|scene| scene.name == name)
+}
+
+/// Empty background for the scenes lit only by their lights.
+fn dark_environment() -> Environment {
+    Environment::solid(color!(0.0, 0.0, 0.0))
+}
+
+/// Camera framing the built-in scenes.
+pub fn default_camera() -> camera::Builder {
+    let look_from = point!(13.0, 2.0, 3.0);
+    let look_at = point!(0.0, 0.0, 0.0);
+
+    let mut builder = camera::Builder::new(look_from, look_at, 20.0, 0.1, 10.0);
+    builder.timed(0.0, 1.0);
+    builder
+}
+
+pub fn random_scene(seed: u64) -> HittableList {
+    let mut rng = SmallRng::seed_from_u64(seed);
     let mut world = HittableList::new();
     let checker = Box::new(CheckerTexture::from_color(
         0.32,
@@ -24,17 +106,17 @@ pub fn random_scene() -> HittableList {
 
     for a in -11..11 {
         for b in -11..11 {
-            let choose_mat = random::<f64>(0.0..=1.0);
+            let choose_mat = random_from::<f64>(&mut rng, 0.0..=1.0);
             let center = point!(
-                a as f64 + 0.9 * random(0.0..=1.0),
+                a as f64 + 0.9 * random_from(&mut rng, 0.0..=1.0),
                 0.2,
-                b as f64 + 0.9 * random(0.0..=1.0)
+                b as f64 + 0.9 * random_from(&mut rng, 0.0..=1.0)
             );
             if (center - point!(4.0, 0.2, 0.0)).len() > 0.9 {
                 if choose_mat < 0.8 {
-                    let albedo = Color::random() * Color::random();
+                    let albedo = Color::random_from(&mut rng) * Color::random_from(&mut rng);
                     let sphere_material = Lambertian::new(albedo);
-                    let center2 = center + vec3!(0.0, random(0.0..=0.5), 0.0);
+                    let center2 = center + vec3!(0.0, random_from(&mut rng, 0.0..=0.5), 0.0);
                     world.add(Arc::new(Sphere::moving(
                         center,
                         center2,
@@ -42,8 +124,8 @@ pub fn random_scene() -> HittableList {
                         sphere_material,
                     )));
                 } else if choose_mat < 0.95 {
-                    let albedo = Color::delimited(0.5..=1.0);
-                    let fuzz = random(0.0..=0.5);
+                    let albedo = Color::delimited_from(&mut rng, 0.5..=1.0);
+                    let fuzz = random_from(&mut rng, 0.0..=0.5);
                     let sphere_material = Metal::new(albedo, fuzz);
                     world.add(Arc::new(Sphere::new(center, 0.2, sphere_material)));
                 } else {
@@ -66,7 +148,8 @@ pub fn random_scene() -> HittableList {
     world
 }
 
-pub fn final_first() -> HittableList {
+pub fn final_first(seed: u64) -> HittableList {
+    let mut rng = SmallRng::seed_from_u64(seed);
     let mut world = HittableList::new();
     let material_ground = Lambertian::new(color!(0.5, 0.5, 0.5));
     world.add(Arc::new(Sphere::new(
@@ -77,20 +160,20 @@ pub fn final_first() -> HittableList {
 
     for a in -11..11 {
         for b in -11..11 {
-            let choose_mat = random::<f64>(0.0..=1.0);
+            let choose_mat = random_from::<f64>(&mut rng, 0.0..=1.0);
             let center = point!(
-                a as f64 + 0.9 * random(0.0..=1.0),
+                a as f64 + 0.9 * random_from(&mut rng, 0.0..=1.0),
                 0.2,
-                b as f64 + 0.9 * random(0.0..=1.0)
+                b as f64 + 0.9 * random_from(&mut rng, 0.0..=1.0)
             );
             if (center - point!(4.0, 0.2, 0.0)).len() > 0.9 {
                 if choose_mat < 0.8 {
-                    let albedo = Color::random() * Color::random();
+                    let albedo = Color::random_from(&mut rng) * Color::random_from(&mut rng);
                     let sphere_material = Lambertian::new(albedo);
                     world.add(Arc::new(Sphere::new(center, 0.2, sphere_material)));
                 } else if choose_mat < 0.95 {
-                    let albedo = Color::delimited(0.5..=1.0);
-                    let fuzz = random(0.0..=0.5);
+                    let albedo = Color::delimited_from(&mut rng, 0.5..=1.0);
+                    let fuzz = random_from(&mut rng, 0.0..=0.5);
                     let sphere_material = Metal::new(albedo, fuzz);
                     world.add(Arc::new(Sphere::new(center, 0.2, sphere_material)));
                 } else {
@@ -139,3 +222,164 @@ pub fn two_sphere() -> HittableList {
     )));
     world
 }
+
+pub fn quads() -> HittableList {
+    let mut world = HittableList::new();
+
+    let left_red = Lambertian::new(color!(1.0, 0.2, 0.2));
+    let back_green = Lambertian::new(color!(0.2, 1.0, 0.2));
+    let right_blue = Lambertian::new(color!(0.2, 0.2, 1.0));
+    let upper_orange = Lambertian::new(color!(1.0, 0.5, 0.0));
+    let lower_teal = Lambertian::new(color!(0.2, 0.8, 0.8));
+
+    world.add(Arc::new(Quad::new(
+        point!(-3.0, -2.0, 5.0),
+        vec3!(0.0, 0.0, -4.0),
+        vec3!(0.0, 4.0, 0.0),
+        left_red,
+    )));
+    world.add(Arc::new(Quad::new(
+        point!(-2.0, -2.0, 0.0),
+        vec3!(4.0, 0.0, 0.0),
+        vec3!(0.0, 4.0, 0.0),
+        back_green,
+    )));
+    world.add(Arc::new(Quad::new(
+        point!(3.0, -2.0, 1.0),
+        vec3!(0.0, 0.0, 4.0),
+        vec3!(0.0, 4.0, 0.0),
+        right_blue,
+    )));
+    world.add(Arc::new(Quad::new(
+        point!(-2.0, 3.0, 1.0),
+        vec3!(4.0, 0.0, 0.0),
+        vec3!(0.0, 0.0, 4.0),
+        upper_orange,
+    )));
+    world.add(Arc::new(Quad::new(
+        point!(-2.0, -3.0, 5.0),
+        vec3!(4.0, 0.0, 0.0),
+        vec3!(0.0, 0.0, -4.0),
+        lower_teal,
+    )));
+
+    world
+}
+
+fn quads_camera() -> camera::Builder {
+    let look_from = point!(0.0, 0.0, 9.0);
+    let look_at = point!(0.0, 0.0, 0.0);
+
+    camera::Builder::new(look_from, look_at, 80.0, 0.0, 10.0)
+}
+
+pub fn simple_light() -> HittableList {
+    let mut world = HittableList::new();
+    let checker = Box::new(CheckerTexture::from_color(
+        0.32,
+        color!(0.2, 0.3, 0.1),
+        color!(0.9, 0.9, 0.9),
+    ));
+
+    world.add(Arc::new(Sphere::new(
+        point!(0.0, -1000.0, 0.0),
+        1000.0,
+        Lambertian::textured(checker),
+    )));
+    world.add(Arc::new(Sphere::new(
+        point!(0.0, 2.0, 0.0),
+        2.0,
+        Lambertian::new(color!(0.4, 0.2, 0.1)),
+    )));
+
+    world.add(Arc::new(Sphere::new(
+        point!(0.0, 7.0, 0.0),
+        2.0,
+        DiffuseLight::new(color!(4.0, 4.0, 4.0)),
+    )));
+    world.add(Arc::new(Quad::new(
+        point!(3.0, 1.0, -2.0),
+        vec3!(2.0, 0.0, 0.0),
+        vec3!(0.0, 2.0, 0.0),
+        DiffuseLight::new(color!(4.0, 4.0, 4.0)),
+    )));
+
+    world
+}
+
+fn simple_light_camera() -> camera::Builder {
+    let look_from = point!(26.0, 3.0, 6.0);
+    let look_at = point!(0.0, 2.0, 0.0);
+
+    camera::Builder::new(look_from, look_at, 20.0, 0.0, 10.0)
+}
+
+pub fn cornell_box() -> HittableList {
+    let mut world = HittableList::new();
+
+    let red = Lambertian::new(color!(0.65, 0.05, 0.05));
+    let white = Arc::new(Lambertian::new(color!(0.73, 0.73, 0.73)));
+    let green = Lambertian::new(color!(0.12, 0.45, 0.15));
+    let light = DiffuseLight::new(color!(15.0, 15.0, 15.0));
+
+    world.add(Arc::new(Quad::new(
+        point!(555.0, 0.0, 0.0),
+        vec3!(0.0, 555.0, 0.0),
+        vec3!(0.0, 0.0, 555.0),
+        green,
+    )));
+    world.add(Arc::new(Quad::new(
+        point!(0.0, 0.0, 0.0),
+        vec3!(0.0, 555.0, 0.0),
+        vec3!(0.0, 0.0, 555.0),
+        red,
+    )));
+    world.add(Arc::new(Quad::new(
+        point!(343.0, 554.0, 332.0),
+        vec3!(-130.0, 0.0, 0.0),
+        vec3!(0.0, 0.0, -105.0),
+        light,
+    )));
+    world.add(Arc::new(Quad::new(
+        point!(0.0, 0.0, 0.0),
+        vec3!(555.0, 0.0, 0.0),
+        vec3!(0.0, 0.0, 555.0),
+        white.clone(),
+    )));
+    world.add(Arc::new(Quad::new(
+        point!(555.0, 555.0, 555.0),
+        vec3!(-555.0, 0.0, 0.0),
+        vec3!(0.0, 0.0, -555.0),
+        white.clone(),
+    )));
+    world.add(Arc::new(Quad::new(
+        point!(0.0, 0.0, 555.0),
+        vec3!(555.0, 0.0, 0.0),
+        vec3!(0.0, 555.0, 0.0),
+        white.clone(),
+    )));
+
+    let tall_box = quad::cuboid(
+        point!(0.0, 0.0, 0.0),
+        point!(165.0, 330.0, 165.0),
+        white.clone(),
+    );
+    world.add(Arc::new(Instance::new(
+        Arc::new(tall_box),
+        Transform::rotate_y(15.0).then(&Transform::translate(vec3!(265.0, 0.0, 295.0))),
+    )));
+    let short_box = quad::cuboid(point!(0.0, 0.0, 0.0), point!(165.0, 165.0, 165.0), white);
+    world.add(Arc::new(Instance::new(
+        Arc::new(short_box),
+        Transform::rotate_y(-18.0).then(&Transform::translate(vec3!(130.0, 0.0, 65.0))),
+    )));
+
+    world
+}
+
+fn cornell_box_camera() -> camera::Builder {
+    let look_from = point!(278.0, 278.0, -800.0);
+    let look_at = point!(278.0, 278.0, 0.0);
+
+    camera::Builder::new(look_from, look_at, 40.0, 0.0, 10.0)
+}
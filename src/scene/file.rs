@@ -0,0 +1,1008 @@
+//! Declarative scene description stored as TOML.
+//!
+//! ```toml
+//! [camera]
+//! look_from = [13.0, 2.0, 3.0]
+//! look_at = [0.0, 0.0, 0.0]
+//! vfov = 20.0
+//! aperture = 0.1
+//! focus_dist = 10.0
+//! time = [0.0, 1.0]
+//!
+//! [settings]
+//! width = 400
+//! samples = 50
+//! seed = 42
+//! sampler = "sobol"
+//! denoiser = "a-trous"
+//! adaptive = { threshold = 0.02, max_samples = 800 }
+//! progressive = { pass_samples = 10, time_budget = 600.0 }
+//! tile_size = 16
+//! tile_order = "hilbert"
+//!
+//! [environment]
+//! type = "map"
+//! path = "sky.hdr"
+//! rotation = 90.0
+//! intensity = 1.5
+//!
+//! [output]
+//! exposure = 0.5
+//! tone_map = "aces"
+//!
+//! [materials.ground]
+//! type = "lambertian"
+//! texture = { type = "checker", scale = 0.32, even = { type = "solid", color = [0.2, 0.3, 0.1] }, odd = { type = "solid", color = [0.9, 0.9, 0.9] } }
+//!
+//! [materials.earth]
+//! type = "lambertian"
+//! texture = { type = "image", path = "earth.png", filter = "bicubic" }
+//!
+//! [materials.tiles]
+//! type = "lambertian"
+//! texture = { type = "mapped", mapping = { type = "triplanar", sharpness = 8.0 }, scale = [0.5, 0.5], rotation = 45.0, texture = { type = "image", path = "tiles.png", wrap = "mirror" } }
+//!
+//! [materials.glass]
+//! type = "dielectric"
+//! refraction_index = 1.5
+//!
+//! [materials.lamp]
+//! type = "diffuse_light"
+//! emit = [4.0, 4.0, 4.0]
+//!
+//! [[objects]]
+//! type = "sphere"
+//! center = [0.0, -1000.0, 0.0]
+//! radius = 1000.0
+//! material = "ground"
+//!
+//! [[objects]]
+//! type = "sphere"
+//! center = [0.0, 1.0, 0.0]
+//! center2 = [0.0, 1.5, 0.0]
+//! radius = 1.0
+//! material = "glass"
+//!
+//! [[objects]]
+//! type = "quad"
+//! q = [-2.0, 0.0, -3.0]
+//! u = [4.0, 0.0, 0.0]
+//! v = [0.0, 3.0, 0.0]
+//! material = "ground"
+//!
+//! [[objects]]
+//! type = "box"
+//! min = [3.0, 0.0, -1.0]
+//! max = [4.0, 1.0, 0.0]
+//! material = "glass"
+//!
+//! [[objects]]
+//! type = "triangle"
+//! a = [-4.0, 0.0, 1.0]
+//! b = [-2.0, 0.0, 1.0]
+//! c = [-3.0, 2.0, 1.0]
+//! material = "ground"
+//!
+//! [[objects]]
+//! type = "obj"
+//! path = "models/teapot.obj"
+//!
+//! # Every object can be transformed, the steps being applied in order. Models
+//! # used several times share their geometry.
+//! [[objects]]
+//! type = "obj"
+//! path = "models/teapot.obj"
+//! transform = [{ scale = [0.5, 0.5, 0.5] }, { rotate_y = 45.0 }, { translate = [2.0, 0.0, 0.0] }]
+//! ```
+
+use crate::bvh::LinearBvh;
+use crate::camera;
+use crate::denoise::UnknownDenoiserError;
+use crate::environment::Environment;
+use crate::geometry::quad::{self, Quad};
+use crate::geometry::sphere::Sphere;
+use crate::geometry::triangle::Triangle;
+use crate::hdr::HdrImage;
+use crate::material::dielectric::Dielectric;
+use crate::material::diffuse_light::DiffuseLight;
+use crate::material::lambertian::Lambertian;
+use crate::material::metal::Metal;
+use crate::material::Material;
+use crate::obj;
+use crate::render::adaptive::AdaptiveSampling;
+use crate::render::progressive::Progressive;
+use crate::render::tile::UnknownTileOrderError;
+use crate::render::RenderSettings;
+use crate::sampler::UnknownSamplerError;
+use crate::texture::checker_texture::CheckerTexture;
+use crate::texture::image_texture::{ImageTexture, UnknownFilterError, UnknownWrapError};
+use crate::texture::mapping::{MappedTexture, Mapping};
+use crate::texture::solid_color::SolidColor;
+use crate::texture::Texture;
+use crate::tonemap::{OutputSettings, UnknownToneMapError};
+use crate::transform::Transform;
+use crate::vec3::{cross, Color, Point3, Vec3};
+use crate::{Hittable, HittableList, Instance};
+use serde::Deserialize;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use toml::Spanned;
+
+/// A scene loaded from a description file, ready to be rendered.
+pub struct LoadedScene {
+    pub camera: camera::Builder,
+    pub settings: RenderSettings,
+    pub environment: Environment,
+    pub output: OutputSettings,
+    pub world: HittableList,
+}
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io {
+        path: PathBuf,
+        source: io::Error,
+    },
+    Syntax(toml::de::Error),
+    Invalid {
+        line: usize,
+        field: String,
+        message: String,
+    },
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SceneError::Io { path, source } => {
+                write!(f, "cannot read {}: {}", path.display(), source)
+            }
+            SceneError::Syntax(err) => write!(f, "{}", err),
+            SceneError::Invalid {
+                line,
+                field,
+                message,
+            } => write!(f, "line {}, field `{}`: {}", line, field, message),
+        }
+    }
+}
+
+impl Error for SceneError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SceneError::Io { source, .. } => Some(source),
+            SceneError::Syntax(err) => Some(err),
+            SceneError::Invalid { .. } => None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneFile {
+    camera: Spanned<CameraDesc>,
+    settings: Option<Spanned<SettingsDesc>>,
+    environment: Option<Spanned<EnvironmentDesc>>,
+    output: Option<Spanned<OutputDesc>>,
+    #[serde(default)]
+    materials: BTreeMap<String, Spanned<MaterialDesc>>,
+    #[serde(default)]
+    objects: Vec<Spanned<ObjectEntry>>,
+}
+
+/// Spans of the values of the file, by table and key, for the errors to point
+/// at the offending value. The descriptions cannot hold them in `Spanned`
+/// fields as serde buffers the internally tagged enums, losing the spans.
+#[derive(Deserialize)]
+struct FieldSpans {
+    camera: Option<KeySpans>,
+    settings: Option<KeySpans>,
+    environment: Option<KeySpans>,
+    output: Option<KeySpans>,
+    #[serde(default)]
+    materials: BTreeMap<String, KeySpans>,
+    #[serde(default)]
+    objects: Vec<KeySpans>,
+}
+
+type KeySpans = BTreeMap<String, Spanned<toml::Value>>;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CameraDesc {
+    look_from: [f64; 3],
+    look_at: [f64; 3],
+    vfov: f64,
+    #[serde(default)]
+    aperture: f64,
+    focus_dist: Option<f64>,
+    vup: Option<[f64; 3]>,
+    time: Option<[f64; 2]>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SettingsDesc {
+    width: Option<usize>,
+    aspect_ratio: Option<f64>,
+    samples: Option<usize>,
+    max_depth: Option<usize>,
+    seed: Option<u64>,
+    sampler: Option<String>,
+    denoiser: Option<String>,
+    adaptive: Option<AdaptiveDesc>,
+    progressive: Option<ProgressiveDesc>,
+    tile_size: Option<usize>,
+    tile_order: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AdaptiveDesc {
+    threshold: f64,
+    max_samples: usize,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProgressiveDesc {
+    pass_samples: Option<usize>,
+    /// In seconds.
+    time_budget: Option<f64>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct OutputDesc {
+    exposure: Option<f64>,
+    tone_map: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EnvironmentDesc {
+    #[serde(rename = "type")]
+    kind: EnvironmentKind,
+    color: Option<[f64; 3]>,
+    bottom: Option<[f64; 3]>,
+    top: Option<[f64; 3]>,
+    path: Option<String>,
+    #[serde(default)]
+    rotation: f64,
+    intensity: Option<f64>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum EnvironmentKind {
+    Solid,
+    Gradient,
+    Map,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum MaterialDesc {
+    Lambertian {
+        albedo: Option<[f64; 3]>,
+        texture: Option<TextureDesc>,
+    },
+    Metal {
+        albedo: [f64; 3],
+        #[serde(default)]
+        fuzziness: f64,
+    },
+    Dielectric {
+        refraction_index: f64,
+    },
+    DiffuseLight {
+        emit: Option<[f64; 3]>,
+        texture: Option<TextureDesc>,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum TextureDesc {
+    Solid {
+        color: [f64; 3],
+    },
+    Checker {
+        scale: f64,
+        even: Box<TextureDesc>,
+        odd: Box<TextureDesc>,
+    },
+    Image {
+        path: String,
+        wrap: Option<String>,
+        filter: Option<String>,
+        mipmap: Option<bool>,
+    },
+    Mapped {
+        texture: Box<TextureDesc>,
+        mapping: MappingDesc,
+        scale: Option<[f64; 2]>,
+        /// Counterclockwise, in degrees.
+        #[serde(default)]
+        rotation: f64,
+        #[serde(default)]
+        offset: [f64; 2],
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum MappingDesc {
+    Uv,
+    Planar {
+        #[serde(default)]
+        origin: [f64; 3],
+        u: [f64; 3],
+        v: [f64; 3],
+    },
+    Cylindrical {
+        #[serde(default)]
+        center: [f64; 3],
+        axis: [f64; 3],
+    },
+    Spherical {
+        #[serde(default)]
+        center: [f64; 3],
+    },
+    Triplanar {
+        sharpness: Option<f64>,
+    },
+}
+
+#[derive(Deserialize)]
+struct ObjectEntry {
+    #[serde(flatten)]
+    shape: ObjectDesc,
+    /// Applied in order, after the object is built.
+    #[serde(default)]
+    transform: Vec<TransformDesc>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum ObjectDesc {
+    Sphere {
+        center: [f64; 3],
+        center2: Option<[f64; 3]>,
+        radius: f64,
+        material: String,
+    },
+    Quad {
+        q: [f64; 3],
+        u: [f64; 3],
+        v: [f64; 3],
+        material: String,
+    },
+    Box {
+        min: [f64; 3],
+        max: [f64; 3],
+        material: String,
+    },
+    Triangle {
+        a: [f64; 3],
+        b: [f64; 3],
+        c: [f64; 3],
+        material: String,
+    },
+    /// Wavefront OBJ model, `material` is used for the faces without one.
+    Obj {
+        path: String,
+        material: Option<String>,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+enum TransformDesc {
+    Translate([f64; 3]),
+    Scale([f64; 3]),
+    RotateX(f64),
+    RotateY(f64),
+    RotateZ(f64),
+    Rotate { axis: [f64; 3], angle: f64 },
+}
+
+impl ObjectDesc {
+    fn material(&self) -> Option<&str> {
+        match self {
+            ObjectDesc::Sphere { material, .. }
+            | ObjectDesc::Quad { material, .. }
+            | ObjectDesc::Box { material, .. }
+            | ObjectDesc::Triangle { material, .. } => Some(material),
+            ObjectDesc::Obj { material, .. } => material.as_deref(),
+        }
+    }
+}
+
+/// Read and build the scene described by the file at `path`.
+pub fn load<P: AsRef<Path>>(path: P) -> Result<LoadedScene, SceneError> {
+    let path = path.as_ref();
+    let source = fs::read_to_string(path).map_err(|source| SceneError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    parse(&source, path.parent().unwrap_or_else(|| Path::new("")))
+}
+
+/// Build the scene described by `source`, resolving the paths it contains
+/// relative to `base_dir`.
+pub fn parse(source: &str, base_dir: &Path) -> Result<LoadedScene, SceneError> {
+    let file: SceneFile = toml::from_str(source).map_err(SceneError::Syntax)?;
+    let spans: FieldSpans = toml::from_str(source).map_err(SceneError::Syntax)?;
+    let invalid = |span: Range<usize>, field: String, message: &str| SceneError::Invalid {
+        line: source[..span.start].matches('\n').count() + 1,
+        field,
+        message: message.to_string(),
+    };
+
+    let mut settings = RenderSettings::default();
+    if let Some(desc) = &file.settings {
+        let at = |key| key_span(spans.settings.as_ref(), key, desc.span());
+        let desc = desc.get_ref();
+        if let Some(ratio) = desc.aspect_ratio {
+            if !(ratio.is_finite() && ratio > 0.0) {
+                return Err(invalid(
+                    at("aspect_ratio"),
+                    "settings.aspect_ratio".to_string(),
+                    "must be a positive number",
+                ));
+            }
+        }
+        if desc.width.is_some() || desc.aspect_ratio.is_some() {
+            settings.resize(
+                desc.width.unwrap_or(settings.image_width),
+                desc.aspect_ratio.unwrap_or(settings.aspect_ratio),
+            );
+        }
+        if settings.image_width < 2 || settings.image_height < 2 {
+            let key = if desc.width.is_some() {
+                "width"
+            } else {
+                "aspect_ratio"
+            };
+            return Err(invalid(
+                at(key),
+                "settings.width".to_string(),
+                "image must be at least 2 pixels wide and high",
+            ));
+        }
+        if let Some(samples) = desc.samples {
+            if samples == 0 {
+                return Err(invalid(
+                    at("samples"),
+                    "settings.samples".to_string(),
+                    "must be at least 1",
+                ));
+            }
+            settings.sample_per_pixel = samples;
+        }
+        if let Some(max_depth) = desc.max_depth {
+            if max_depth == 0 {
+                return Err(invalid(
+                    at("max_depth"),
+                    "settings.max_depth".to_string(),
+                    "must be at least 1",
+                ));
+            }
+            settings.max_depth = max_depth;
+        }
+        if let Some(seed) = desc.seed {
+            settings.seed = seed;
+        }
+        if let Some(sampler) = &desc.sampler {
+            settings.sampler = sampler.parse().map_err(|err: UnknownSamplerError| {
+                invalid(
+                    at("sampler"),
+                    "settings.sampler".to_string(),
+                    &err.to_string(),
+                )
+            })?;
+        }
+        if let Some(adaptive) = &desc.adaptive {
+            if !(adaptive.threshold.is_finite() && adaptive.threshold > 0.0) {
+                return Err(invalid(
+                    at("adaptive"),
+                    "settings.adaptive.threshold".to_string(),
+                    "must be a positive number",
+                ));
+            }
+            if adaptive.max_samples == 0 {
+                return Err(invalid(
+                    at("adaptive"),
+                    "settings.adaptive.max_samples".to_string(),
+                    "must be at least 1",
+                ));
+            }
+            settings.adaptive = Some(AdaptiveSampling::new(
+                adaptive.threshold,
+                adaptive.max_samples,
+            ));
+        }
+        if let Some(progressive_desc) = &desc.progressive {
+            let mut progressive = Progressive::default();
+            if let Some(pass_samples) = progressive_desc.pass_samples {
+                if pass_samples == 0 {
+                    return Err(invalid(
+                        at("progressive"),
+                        "settings.progressive.pass_samples".to_string(),
+                        "must be at least 1",
+                    ));
+                }
+                progressive.pass_samples = pass_samples;
+            }
+            if let Some(time_budget) = progressive_desc.time_budget {
+                if !(time_budget.is_finite() && time_budget > 0.0) {
+                    return Err(invalid(
+                        at("progressive"),
+                        "settings.progressive.time_budget".to_string(),
+                        "must be a positive number of seconds",
+                    ));
+                }
+                progressive.time_budget = Some(Duration::from_secs_f64(time_budget));
+            }
+            settings.progressive = Some(progressive);
+        }
+        if let Some(tile_size) = desc.tile_size {
+            if tile_size == 0 {
+                return Err(invalid(
+                    at("tile_size"),
+                    "settings.tile_size".to_string(),
+                    "must be at least 1",
+                ));
+            }
+            settings.tile_size = tile_size;
+        }
+        if let Some(tile_order) = &desc.tile_order {
+            settings.tile_order = tile_order.parse().map_err(|err: UnknownTileOrderError| {
+                invalid(
+                    at("tile_order"),
+                    "settings.tile_order".to_string(),
+                    &err.to_string(),
+                )
+            })?;
+        }
+        if let Some(denoiser) = &desc.denoiser {
+            settings.denoiser = denoiser.parse().map_err(|err: UnknownDenoiserError| {
+                invalid(
+                    at("denoiser"),
+                    "settings.denoiser".to_string(),
+                    &err.to_string(),
+                )
+            })?;
+        }
+    }
+
+    let mut output = OutputSettings::default();
+    if let Some(desc) = &file.output {
+        if let Some(exposure) = desc.get_ref().exposure {
+            output.exposure = exposure;
+        }
+        if let Some(tone_map) = &desc.get_ref().tone_map {
+            output.tone_map = tone_map.parse().map_err(|err: UnknownToneMapError| {
+                invalid(
+                    key_span(spans.output.as_ref(), "tone_map", desc.span()),
+                    "output.tone_map".to_string(),
+                    &err.to_string(),
+                )
+            })?;
+        }
+    }
+
+    let environment = match &file.environment {
+        Some(desc) => build_environment(desc.get_ref(), base_dir).map_err(|(key, message)| {
+            invalid(
+                key_span(spans.environment.as_ref(), key, desc.span()),
+                format!("environment.{}", key),
+                &message,
+            )
+        })?,
+        None => Environment::default(),
+    };
+
+    let camera = {
+        let at = |key| key_span(spans.camera.as_ref(), key, file.camera.span());
+        let desc = file.camera.get_ref();
+        let look_from = Point3::from(desc.look_from);
+        let look_at = Point3::from(desc.look_at);
+        let focus_dist = desc
+            .focus_dist
+            .unwrap_or_else(|| (look_from - look_at).len());
+        let mut builder =
+            camera::Builder::new(look_from, look_at, desc.vfov, desc.aperture, focus_dist);
+        builder.aspect_ratio(settings.aspect_ratio);
+        if let Some(vup) = desc.vup {
+            builder.tilted(Vec3::from(vup));
+        }
+        builder
+            .validate()
+            .map_err(|(key, message)| invalid(at(key), format!("camera.{}", key), message))?;
+        if let Some([start, end]) = desc.time {
+            builder.timed(start, end);
+        }
+        builder
+    };
+
+    let mut materials = BTreeMap::new();
+    for (name, desc) in &file.materials {
+        let field = format!("materials.{}", name);
+        let material = build_material(desc.get_ref(), base_dir).map_err(|(key, message)| {
+            invalid(
+                key_span(spans.materials.get(name), key, desc.span()),
+                format!("{}.{}", field, key),
+                &message,
+            )
+        })?;
+        materials.insert(name.as_str(), material);
+    }
+
+    let mut objects = HittableList::new();
+    // Models referenced several times share their geometry.
+    let mut models: HashMap<_, Vec<Arc<dyn Hittable>>> = HashMap::new();
+    for (index, entry) in file.objects.iter().enumerate() {
+        let field = format!("objects[{}]", index);
+        let at = |key| key_span(spans.objects.get(index), key, entry.span());
+        let desc = &entry.get_ref().shape;
+        let material = match desc.material() {
+            Some(name) => materials.get(name).cloned().ok_or_else(|| {
+                invalid(
+                    at("material"),
+                    format!("{}.material", field),
+                    &format!("unknown material `{}`", name),
+                )
+            })?,
+            None => Arc::new(Lambertian::new(color!(0.5, 0.5, 0.5))),
+        };
+
+        let mut parts: Vec<Arc<dyn Hittable>> = match desc {
+            ObjectDesc::Sphere {
+                center,
+                center2,
+                radius,
+                ..
+            } => {
+                if !(radius.is_finite() && *radius > 0.0) {
+                    return Err(invalid(
+                        at("radius"),
+                        format!("{}.radius", field),
+                        "must be positive",
+                    ));
+                }
+                let center = Point3::from(*center);
+                match center2 {
+                    Some(center2) => vec![Arc::new(Sphere::moving(
+                        center,
+                        Point3::from(*center2),
+                        *radius,
+                        material,
+                    ))],
+                    None => vec![Arc::new(Sphere::new(center, *radius, material))],
+                }
+            }
+            ObjectDesc::Quad { q, u, v, .. } => {
+                let (u, v) = (Vec3::from(*u), Vec3::from(*v));
+                if cross(u, v).near_zero() {
+                    return Err(invalid(
+                        at("v"),
+                        format!("{}.v", field),
+                        "edges must not be parallel",
+                    ));
+                }
+                vec![Arc::new(Quad::new(Point3::from(*q), u, v, material))]
+            }
+            ObjectDesc::Box { min, max, .. } => {
+                for (key, corner) in [("min", min), ("max", max)] {
+                    if !corner.iter().all(|c| c.is_finite()) {
+                        return Err(invalid(
+                            at(key),
+                            format!("{}.{}", field, key),
+                            "must be finite",
+                        ));
+                    }
+                }
+                if min.iter().zip(max).any(|(a, b)| (a - b).abs() < 1e-8) {
+                    return Err(invalid(
+                        at("max"),
+                        format!("{}.max", field),
+                        "corners must differ on every axis",
+                    ));
+                }
+                let sides = quad::cuboid(Point3::from(*min), Point3::from(*max), material);
+                sides.objects().to_vec()
+            }
+            ObjectDesc::Triangle { a, b, c, .. } => {
+                let (a, b, c) = (Point3::from(*a), Point3::from(*b), Point3::from(*c));
+                if cross(b - a, c - a).near_zero() {
+                    return Err(invalid(
+                        at("c"),
+                        format!("{}.c", field),
+                        "vertices must not be aligned",
+                    ));
+                }
+                vec![Arc::new(Triangle::new(a, b, c, material))]
+            }
+            ObjectDesc::Obj {
+                path,
+                material: name,
+            } => match models.entry((path.as_str(), name.as_deref())) {
+                Entry::Occupied(model) => model.get().clone(),
+                Entry::Vacant(slot) => {
+                    let model = obj::load(base_dir.join(path), material).map_err(|err| {
+                        invalid(at("path"), format!("{}.path", field), &err.to_string())
+                    })?;
+                    slot.insert(model.into_list().objects().to_vec()).clone()
+                }
+            },
+        };
+
+        let transform = entry.get_ref().transform.iter().enumerate().try_fold(
+            Transform::identity(),
+            |transform, (step, desc)| {
+                build_transform(desc)
+                    .map(|next| transform.then(&next))
+                    .map_err(|message| {
+                        invalid(
+                            at("transform"),
+                            format!("{}.transform[{}]", field, step),
+                            message,
+                        )
+                    })
+            },
+        )?;
+
+        if entry.get_ref().transform.is_empty() {
+            for part in parts {
+                objects.add(part);
+            }
+        } else {
+            let object: Arc<dyn Hittable> = if parts.len() == 1 {
+                parts.remove(0)
+            } else {
+                let mut list = HittableList::new();
+                for part in parts {
+                    list.add(part);
+                }
+                Arc::new(list)
+            };
+            objects.add(Arc::new(Instance::new(object, transform)));
+        }
+    }
+
+    let mut world = HittableList::new();
+    if !objects.objects().is_empty() {
+        world.add(Arc::new(LinearBvh::new(objects)));
+    }
+
+    Ok(LoadedScene {
+        camera,
+        settings,
+        environment,
+        output,
+        world,
+    })
+}
+
+/// The span of the value of `key` in `keys`, only the first part of nested
+/// keys like `adaptive.threshold` being looked up, or `fallback` when the key
+/// is missing.
+fn key_span(keys: Option<&KeySpans>, key: &str, fallback: Range<usize>) -> Range<usize> {
+    let key = key.split(['.', '[']).next().unwrap_or(key);
+    keys.and_then(|keys| keys.get(key))
+        .map_or(fallback, Spanned::span)
+}
+
+fn build_transform(desc: &TransformDesc) -> Result<Transform, &'static str> {
+    let transform = match desc {
+        TransformDesc::Translate(offset) => Transform::translate(Vec3::from(*offset)),
+        TransformDesc::Scale(factors) => {
+            if factors.contains(&0.0) {
+                return Err("scale factors must not be 0");
+            }
+            Transform::scale(Vec3::from(*factors))
+        }
+        TransformDesc::RotateX(angle) => Transform::rotate_x(*angle),
+        TransformDesc::RotateY(angle) => Transform::rotate_y(*angle),
+        TransformDesc::RotateZ(angle) => Transform::rotate_z(*angle),
+        TransformDesc::Rotate { axis, angle } => {
+            let axis = Vec3::from(*axis);
+            if axis.near_zero() {
+                return Err("rotation axis must not be null");
+            }
+            Transform::rotate(axis, *angle)
+        }
+    };
+    Ok(transform)
+}
+
+fn build_environment(
+    desc: &EnvironmentDesc,
+    base_dir: &Path,
+) -> Result<Environment, (&'static str, String)> {
+    let missing = |key: &'static str| (key, String::from("required by this environment type"));
+    let environment = match desc.kind {
+        EnvironmentKind::Solid => {
+            Environment::solid(Color::from(desc.color.ok_or(missing("color"))?))
+        }
+        EnvironmentKind::Gradient => Environment::gradient(
+            Color::from(desc.bottom.ok_or(missing("bottom"))?),
+            Color::from(desc.top.ok_or(missing("top"))?),
+        ),
+        EnvironmentKind::Map => {
+            let path = base_dir.join(desc.path.as_ref().ok_or(missing("path"))?);
+            let image = HdrImage::load(&path)
+                .map_err(|err| ("path", format!("cannot load {}: {}", path.display(), err)))?;
+            Environment::map(Arc::new(image))
+        }
+    };
+
+    let intensity = desc.intensity.unwrap_or(1.0);
+    if !(intensity.is_finite() && intensity >= 0.0) {
+        return Err(("intensity", String::from("must not be negative")));
+    }
+    Ok(environment.rotated(desc.rotation).scaled(intensity))
+}
+
+fn build_material(
+    desc: &MaterialDesc,
+    base_dir: &Path,
+) -> Result<Arc<dyn Material>, (&'static str, String)> {
+    Ok(match desc {
+        MaterialDesc::Lambertian {
+            albedo: Some(albedo),
+            texture: None,
+        } => Arc::new(Lambertian::new(Vec3::from(*albedo))),
+        MaterialDesc::Lambertian {
+            albedo: None,
+            texture: Some(texture),
+        } => Arc::new(Lambertian::textured(
+            build_texture(texture, base_dir).map_err(|message| ("texture", message))?,
+        )),
+        MaterialDesc::Lambertian { .. } => {
+            return Err((
+                "albedo",
+                "exactly one of `albedo` or `texture` is required".to_string(),
+            ))
+        }
+        MaterialDesc::Metal { albedo, fuzziness } => {
+            if !(fuzziness.is_finite() && *fuzziness >= 0.0) {
+                return Err(("fuzziness", "must not be negative".to_string()));
+            }
+            Arc::new(Metal::new(Vec3::from(*albedo), *fuzziness))
+        }
+        MaterialDesc::DiffuseLight {
+            emit: Some(emit),
+            texture: None,
+        } => Arc::new(DiffuseLight::new(Color::from(*emit))),
+        MaterialDesc::DiffuseLight {
+            emit: None,
+            texture: Some(texture),
+        } => Arc::new(DiffuseLight::textured(
+            build_texture(texture, base_dir).map_err(|message| ("texture", message))?,
+        )),
+        MaterialDesc::DiffuseLight { .. } => {
+            return Err((
+                "emit",
+                "exactly one of `emit` or `texture` is required".to_string(),
+            ))
+        }
+        MaterialDesc::Dielectric { refraction_index } => {
+            if !(refraction_index.is_finite() && *refraction_index > 0.0) {
+                return Err(("refraction_index", "must be positive".to_string()));
+            }
+            Arc::new(Dielectric::new(*refraction_index))
+        }
+    })
+}
+
+fn build_texture(desc: &TextureDesc, base_dir: &Path) -> Result<Box<dyn Texture>, String> {
+    Ok(match desc {
+        TextureDesc::Solid { color } => Box::new(SolidColor::from(Vec3::from(*color))),
+        TextureDesc::Checker { scale, even, odd } => {
+            if !(scale.is_finite() && *scale > 0.0) {
+                return Err("checker scale must be positive".to_string());
+            }
+            Box::new(CheckerTexture::new(
+                *scale,
+                build_texture(even, base_dir)?,
+                build_texture(odd, base_dir)?,
+            ))
+        }
+        TextureDesc::Mapped {
+            texture,
+            mapping,
+            scale,
+            rotation,
+            offset,
+        } => {
+            let [scale_u, scale_v] = scale.unwrap_or([1.0, 1.0]);
+            if scale_u == 0.0 || scale_v == 0.0 {
+                return Err("mapping scale must not be 0".to_string());
+            }
+            Box::new(
+                MappedTexture::new(
+                    build_texture(texture, base_dir)?,
+                    build_mapping(mapping).map_err(str::to_string)?,
+                )
+                .scaled(scale_u, scale_v)
+                .rotated(*rotation)
+                .offset(offset[0], offset[1]),
+            )
+        }
+        TextureDesc::Image {
+            path,
+            wrap,
+            filter,
+            mipmap,
+        } => {
+            let path = base_dir.join(path);
+            let mut texture = ImageTexture::load(&path)
+                .map_err(|err| format!("cannot load {}: {}", path.display(), err))?;
+            if let Some(wrap) = wrap {
+                texture = texture.wrapped(
+                    wrap.parse()
+                        .map_err(|err: UnknownWrapError| err.to_string())?,
+                );
+            }
+            if let Some(filter) = filter {
+                texture = texture.filtered(
+                    filter
+                        .parse()
+                        .map_err(|err: UnknownFilterError| err.to_string())?,
+                );
+            }
+            if *mipmap == Some(false) {
+                texture = texture.without_mipmaps();
+            }
+            Box::new(texture)
+        }
+    })
+}
+
+fn build_mapping(desc: &MappingDesc) -> Result<Mapping, &'static str> {
+    Ok(match desc {
+        MappingDesc::Uv => Mapping::Uv,
+        MappingDesc::Planar { origin, u, v } => {
+            let (u, v) = (Vec3::from(*u), Vec3::from(*v));
+            if cross(u, v).near_zero() {
+                return Err("planar mapping vectors must not be null or parallel");
+            }
+            Mapping::Planar {
+                origin: Point3::from(*origin),
+                u,
+                v,
+            }
+        }
+        MappingDesc::Cylindrical { center, axis } => {
+            let axis = Vec3::from(*axis);
+            if axis.near_zero() {
+                return Err("cylindrical mapping axis must not be null");
+            }
+            Mapping::Cylindrical {
+                center: Point3::from(*center),
+                axis,
+            }
+        }
+        MappingDesc::Spherical { center } => Mapping::Spherical {
+            center: Point3::from(*center),
+        },
+        MappingDesc::Triplanar { sharpness } => {
+            let sharpness = sharpness.unwrap_or(4.0);
+            if !(sharpness.is_finite() && sharpness > 0.0) {
+                return Err("triplanar sharpness must be positive");
+            }
+            Mapping::Triplanar { sharpness }
+        }
+    })
+}
@@ -1,8 +1,17 @@
+use crate::hit::Hit;
 use crate::vec3::{Color, Point3};
 
 pub mod checker_texture;
+pub mod image_texture;
+pub mod mapping;
 pub mod solid_color;
 
 pub trait Texture: Sync + Send {
     fn value(&self, u: f64, v: f64, p: Point3) -> Color;
+
+    /// Color at a hit point, for the textures depending on more than its
+    /// coordinates, like the normal. Defaults to `value`.
+    fn value_at(&self, hit: &Hit) -> Color {
+        self.value(hit.u, hit.v, hit.p)
+    }
 }
@@ -1,5 +1,6 @@
 use super::{solid_color::SolidColor, Texture};
-use crate::vec3::Color;
+use crate::hit::{Footprint, Hit};
+use crate::vec3::{Color, Point3};
 
 pub struct CheckerTexture {
     inv_scale: f64,
@@ -25,16 +26,64 @@ impl CheckerTexture {
     }
 }
 
-impl Texture for CheckerTexture {
-    fn value(&self, u: f64, v: f64, p: crate::vec3::Point3) -> crate::vec3::Color {
+impl CheckerTexture {
+    fn is_even(&self, p: Point3) -> bool {
         let x = f64::floor(self.inv_scale * p.x()) as i32;
         let y = f64::floor(self.inv_scale * p.y()) as i32;
         let z = f64::floor(self.inv_scale * p.z()) as i32;
+        (x + y + z) % 2 == 0
+    }
+}
 
-        if (x + y + z) % 2 == 0 {
+impl Texture for CheckerTexture {
+    fn value(&self, u: f64, v: f64, p: Point3) -> Color {
+        if self.is_even(p) {
             self.even.value(u, v, p)
         } else {
             self.odd.value(u, v, p)
         }
     }
+
+    /// Blend of the two textures in the proportions of their cells in a box
+    /// around the hit point, as large as its footprint along each axis, so
+    /// that distant cells average out instead of aliasing.
+    fn value_at(&self, hit: &Hit) -> Color {
+        let Footprint { dpdx, dpdy, .. } = hit.footprint;
+        let mut parity = 1.0;
+        for axis in 0..3 {
+            let radius = 0.5 * self.inv_scale * dpdx[axis].abs().max(dpdy[axis].abs());
+            parity *= average_square_wave(self.inv_scale * hit.p[axis], radius);
+        }
+
+        let even = 0.5 * (1.0 + parity);
+        if even >= 1.0 {
+            self.even.value_at(hit)
+        } else if even <= 0.0 {
+            self.odd.value_at(hit)
+        } else {
+            even * self.even.value_at(hit) + (1.0 - even) * self.odd.value_at(hit)
+        }
+    }
+}
+
+/// Average over `[x - radius, x + radius]` of the wave equal to 1 over the
+/// even cells `[2k, 2k + 1)` and to -1 over the odd ones.
+fn average_square_wave(x: f64, radius: f64) -> f64 {
+    if radius < 1e-9 {
+        return if x.floor().rem_euclid(2.0) == 0.0 {
+            1.0
+        } else {
+            -1.0
+        };
+    }
+    // Integral of the wave from 0, a triangle wave.
+    let integral = |x: f64| {
+        let x = x.rem_euclid(2.0);
+        if x < 1.0 {
+            x
+        } else {
+            2.0 - x
+        }
+    };
+    (integral(x + radius) - integral(x - radius)) / (2.0 * radius)
 }
@@ -0,0 +1,411 @@
+//! Textures read from PNG and Radiance HDR images.
+
+use super::Texture;
+use crate::hdr::{HdrError, HdrImage};
+use crate::hit::Hit;
+use crate::tonemap;
+use crate::vec3::{Color, Point3};
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// How coordinates outside of [0, 1] are brought back onto the image.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Wrap {
+    /// Tile the image.
+    #[default]
+    Repeat,
+    /// Extend the pixels along the edges.
+    Clamp,
+    /// Tile the image, flipping every other copy so that the edges match.
+    Mirror,
+}
+
+impl Wrap {
+    pub const ALL: [Wrap; 3] = [Wrap::Repeat, Wrap::Clamp, Wrap::Mirror];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Wrap::Repeat => "repeat",
+            Wrap::Clamp => "clamp",
+            Wrap::Mirror => "mirror",
+        }
+    }
+
+    /// Index of the pixel at `index` on a line of `size` pixels.
+    fn apply(&self, index: i64, size: usize) -> usize {
+        let size = size as i64;
+        let index = match self {
+            Wrap::Repeat => index.rem_euclid(size),
+            Wrap::Clamp => index.clamp(0, size - 1),
+            Wrap::Mirror => {
+                let index = index.rem_euclid(2 * size);
+                if index < size {
+                    index
+                } else {
+                    2 * size - 1 - index
+                }
+            }
+        };
+        index as usize
+    }
+}
+
+impl fmt::Display for Wrap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Debug)]
+pub struct UnknownWrapError(String);
+
+impl fmt::Display for UnknownWrapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names: Vec<_> = Wrap::ALL.iter().map(Wrap::name).collect();
+        write!(
+            f,
+            "unknown wrap mode `{}`, expected one of {}",
+            self.0,
+            names.join(", ")
+        )
+    }
+}
+
+impl Error for UnknownWrapError {}
+
+impl FromStr for Wrap {
+    type Err = UnknownWrapError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Wrap::ALL
+            .into_iter()
+            .find(|wrap| wrap.name() == s)
+            .ok_or_else(|| UnknownWrapError(s.to_string()))
+    }
+}
+
+/// How the pixels around the coordinates are combined.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Filter {
+    /// The closest pixel, keeping the pixels sharp when magnified.
+    Nearest,
+    /// Linear interpolation between the 4 closest pixels.
+    #[default]
+    Bilinear,
+    /// Catmull-Rom interpolation between the 16 closest pixels, smoother
+    /// when magnified.
+    Bicubic,
+}
+
+impl Filter {
+    pub const ALL: [Filter; 3] = [Filter::Nearest, Filter::Bilinear, Filter::Bicubic];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Filter::Nearest => "nearest",
+            Filter::Bilinear => "bilinear",
+            Filter::Bicubic => "bicubic",
+        }
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Debug)]
+pub struct UnknownFilterError(String);
+
+impl fmt::Display for UnknownFilterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names: Vec<_> = Filter::ALL.iter().map(Filter::name).collect();
+        write!(
+            f,
+            "unknown filter `{}`, expected one of {}",
+            self.0,
+            names.join(", ")
+        )
+    }
+}
+
+impl Error for UnknownFilterError {}
+
+impl FromStr for Filter {
+    type Err = UnknownFilterError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Filter::ALL
+            .into_iter()
+            .find(|filter| filter.name() == s)
+            .ok_or_else(|| UnknownFilterError(s.to_string()))
+    }
+}
+
+#[derive(Debug)]
+pub enum ImageTextureError {
+    Io(io::Error),
+    Png(png::DecodingError),
+    Hdr(HdrError),
+    /// The extension of the file is neither `png` nor `hdr`.
+    UnsupportedFormat(String),
+    /// The image has no pixel.
+    Empty,
+}
+
+impl fmt::Display for ImageTextureError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ImageTextureError::Io(err) => write!(f, "{}", err),
+            ImageTextureError::Png(err) => write!(f, "invalid PNG file: {}", err),
+            ImageTextureError::Hdr(err) => write!(f, "{}", err),
+            ImageTextureError::UnsupportedFormat(extension) => write!(
+                f,
+                "unsupported image format `{}`, expected png or hdr",
+                extension
+            ),
+            ImageTextureError::Empty => write!(f, "empty image"),
+        }
+    }
+}
+
+impl Error for ImageTextureError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ImageTextureError::Io(err) => Some(err),
+            ImageTextureError::Png(err) => Some(err),
+            ImageTextureError::Hdr(err) => Some(err),
+            ImageTextureError::UnsupportedFormat(_) | ImageTextureError::Empty => None,
+        }
+    }
+}
+
+impl From<io::Error> for ImageTextureError {
+    fn from(err: io::Error) -> Self {
+        ImageTextureError::Io(err)
+    }
+}
+
+impl From<png::DecodingError> for ImageTextureError {
+    fn from(err: png::DecodingError) -> Self {
+        ImageTextureError::Png(err)
+    }
+}
+
+impl From<HdrError> for ImageTextureError {
+    fn from(err: HdrError) -> Self {
+        ImageTextureError::Hdr(err)
+    }
+}
+
+/// An image stretched over the [0, 1] square of the surface coordinates,
+/// `v` going up from the bottom row.
+///
+/// Where the image is minified, lookups blend the two reductions of the
+/// image whose pixels are closest in size to the footprint of the hit
+/// (trilinear mipmapping), instead of aliasing.
+pub struct ImageTexture {
+    /// The image followed by its reductions by half down to a single pixel,
+    /// when mipmapped.
+    levels: Vec<Arc<HdrImage>>,
+    wrap: Wrap,
+    filter: Filter,
+}
+
+impl ImageTexture {
+    /// # Panics
+    ///
+    /// Panics if the image has no pixel.
+    pub fn new(image: Arc<HdrImage>) -> Self {
+        assert!(image.width() > 0 && image.height() > 0, "empty image");
+        let mut levels = vec![image];
+        while let Some(level) = levels
+            .last()
+            .filter(|level| level.width() * level.height() > 1)
+        {
+            levels.push(Arc::new(reduce(level)));
+        }
+        ImageTexture {
+            levels,
+            wrap: Wrap::default(),
+            filter: Filter::default(),
+        }
+    }
+
+    /// Read a PNG image, whose colors are taken as sRGB encoded, or a linear
+    /// Radiance HDR image, according to the extension of `path`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ImageTextureError> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase)
+            .unwrap_or_default();
+        let image = match extension.as_str() {
+            "png" => load_png(path)?,
+            "hdr" => HdrImage::load(path)?,
+            _ => return Err(ImageTextureError::UnsupportedFormat(extension)),
+        };
+        if image.width() == 0 || image.height() == 0 {
+            return Err(ImageTextureError::Empty);
+        }
+        Ok(ImageTexture::new(Arc::new(image)))
+    }
+
+    pub fn wrapped(mut self, wrap: Wrap) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    pub fn filtered(mut self, filter: Filter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Always look the full resolution image up, however small the image
+    /// appears.
+    pub fn without_mipmaps(mut self) -> Self {
+        self.levels.truncate(1);
+        self
+    }
+
+    /// Filter the image of the given level at `(u, v)`.
+    fn lookup(&self, level: usize, u: f64, v: f64) -> Color {
+        let image = &*self.levels[level];
+        let pixel = |x: i64, y: i64| {
+            image.pixel(
+                self.wrap.apply(x, image.width()),
+                self.wrap.apply(y, image.height()),
+            )
+        };
+
+        // Pixel coordinates with the centers of the pixels at integers.
+        let x = u * image.width() as f64 - 0.5;
+        let y = (1.0 - v) * image.height() as f64 - 0.5;
+        if !(x.is_finite() && y.is_finite()) {
+            return Color::default();
+        }
+        let (x0, y0) = (x.floor(), y.floor());
+        let (tx, ty) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        match self.filter {
+            Filter::Nearest => pixel(x.round() as i64, y.round() as i64),
+            Filter::Bilinear => {
+                let top = (1.0 - tx) * pixel(x0, y0) + tx * pixel(x0 + 1, y0);
+                let bottom = (1.0 - tx) * pixel(x0, y0 + 1) + tx * pixel(x0 + 1, y0 + 1);
+                (1.0 - ty) * top + ty * bottom
+            }
+            Filter::Bicubic => {
+                let (wx, wy) = (catmull_rom(tx), catmull_rom(ty));
+                let mut color = Color::default();
+                for (j, wy) in (-1..=2).zip(wy) {
+                    for (i, wx) in (-1..=2).zip(wx) {
+                        color += wx * wy * pixel(x0 + i, y0 + j);
+                    }
+                }
+                // The negative lobes of the filter can overshoot below 0.
+                color!(color.x().max(0.0), color.y().max(0.0), color.z().max(0.0))
+            }
+        }
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f64, v: f64, _: Point3) -> Color {
+        self.lookup(0, u, v)
+    }
+
+    fn value_at(&self, hit: &Hit) -> Color {
+        let footprint = &hit.footprint;
+        let (width, height) = (
+            self.levels[0].width() as f64,
+            self.levels[0].height() as f64,
+        );
+        // Size of the footprint in pixels of the full resolution image.
+        let size = f64::max(
+            (footprint.dudx * width).hypot(footprint.dvdx * height),
+            (footprint.dudy * width).hypot(footprint.dvdy * height),
+        );
+        let level = size.max(1.0).log2().min((self.levels.len() - 1) as f64);
+
+        let (coarse, t) = (level.ceil() as usize, level.fract());
+        let fine = level.floor() as usize;
+        if fine == coarse {
+            self.lookup(fine, hit.u, hit.v)
+        } else {
+            (1.0 - t) * self.lookup(fine, hit.u, hit.v) + t * self.lookup(coarse, hit.u, hit.v)
+        }
+    }
+}
+
+/// Halve the resolution of an image, averaging blocks of 2 by 2 pixels. The
+/// last row and column of images of odd size are repeated.
+fn reduce(image: &HdrImage) -> HdrImage {
+    let (width, height) = (image.width(), image.height());
+    let mut reduced = HdrImage::new(width.div_ceil(2), height.div_ceil(2));
+    for y in 0..reduced.height() {
+        for x in 0..reduced.width() {
+            let (x0, y0) = (2 * x, 2 * y);
+            let (x1, y1) = ((x0 + 1).min(width - 1), (y0 + 1).min(height - 1));
+            let sum = image.pixel(x0, y0)
+                + image.pixel(x1, y0)
+                + image.pixel(x0, y1)
+                + image.pixel(x1, y1);
+            reduced.set_pixel(x, y, sum / 4.0);
+        }
+    }
+    reduced
+}
+
+/// Weights of the 4 pixels around a point at `t` between the middle two.
+fn catmull_rom(t: f64) -> [f64; 4] {
+    let (t2, t3) = (t * t, t * t * t);
+    [
+        0.5 * (-t3 + 2.0 * t2 - t),
+        0.5 * (3.0 * t3 - 5.0 * t2 + 2.0),
+        0.5 * (-3.0 * t3 + 4.0 * t2 + t),
+        0.5 * (t3 - t2),
+    ]
+}
+
+/// Read a PNG image, decoding its sRGB colors to linear ones. The alpha
+/// channel is ignored.
+fn load_png(path: &Path) -> Result<HdrImage, ImageTextureError> {
+    let mut decoder = png::Decoder::new(BufReader::new(File::open(path)?));
+    decoder.set_transformations(png::Transformations::EXPAND);
+    let mut reader = decoder.read_info()?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer)?;
+
+    let channels = info.color_type.samples();
+    let sample = |bytes: &[u8], index: usize| match info.bit_depth {
+        png::BitDepth::Sixteen => {
+            u16::from_be_bytes([bytes[2 * index], bytes[2 * index + 1]]) as f64 / 65535.0
+        }
+        _ => bytes[index] as f64 / 255.0,
+    };
+
+    let (width, height) = (info.width as usize, info.height as usize);
+    let mut image = HdrImage::new(width, height);
+    for (y, row) in buffer.chunks(info.line_size).take(height).enumerate() {
+        for x in 0..width {
+            let decode = |channel| tonemap::srgb_decode(sample(row, x * channels + channel));
+            let color = match info.color_type {
+                png::ColorType::Grayscale | png::ColorType::GrayscaleAlpha => {
+                    let value = decode(0);
+                    color!(value, value, value)
+                }
+                _ => color!(decode(0), decode(1), decode(2)),
+            };
+            image.set_pixel(x, y, color);
+        }
+    }
+    Ok(image)
+}
@@ -0,0 +1,199 @@
+//! Texture coordinates computed from the position of the hit point instead of
+//! the surface coordinates, then scaled, rotated and offset before the lookup.
+
+use super::Texture;
+use crate::geometry::sphere::sphere_uv;
+use crate::hit::{Footprint, Hit};
+use crate::vec3::{cross, dot, unit_vector, Color, Point3, Vec3};
+use std::f64::consts::PI;
+
+#[derive(Copy, Clone)]
+pub enum Mapping {
+    /// The surface coordinates of the hit.
+    Uv,
+    /// Projection onto the plane spanned by `u` and `v` from `origin`, the
+    /// texture being repeated once along each vector.
+    Planar { origin: Point3, u: Vec3, v: Vec3 },
+    /// Angle around the axis through `center`, and height along it, the
+    /// texture covering `axis` once.
+    Cylindrical { center: Point3, axis: Vec3 },
+    /// Longitude and latitude around `center`, as on spheres.
+    Spherical { center: Point3 },
+    /// Planar projections along the X, Y and Z axes, blended by the normal.
+    /// Higher `sharpness` narrows the transitions between them.
+    Triplanar { sharpness: f64 },
+}
+
+/// A texture looked up at the coordinates given by a mapping.
+pub struct MappedTexture {
+    texture: Box<dyn Texture>,
+    mapping: Mapping,
+    scale: (f64, f64),
+    rotation: f64,
+    offset: (f64, f64),
+}
+
+impl MappedTexture {
+    pub fn new(texture: Box<dyn Texture>, mapping: Mapping) -> Self {
+        MappedTexture {
+            texture,
+            mapping,
+            scale: (1.0, 1.0),
+            rotation: 0.0,
+            offset: (0.0, 0.0),
+        }
+    }
+
+    /// Multiply the coordinates, repeating the texture more often.
+    pub fn scaled(mut self, u: f64, v: f64) -> Self {
+        self.scale = (u, v);
+        self
+    }
+
+    /// Rotate the coordinates counterclockwise after scaling them, angle in
+    /// degrees.
+    pub fn rotated(mut self, angle: f64) -> Self {
+        self.rotation = angle.to_radians();
+        self
+    }
+
+    /// Shift the coordinates after scaling and rotating them.
+    pub fn offset(mut self, u: f64, v: f64) -> Self {
+        self.offset = (u, v);
+        self
+    }
+
+    fn transform(&self, uv: (f64, f64)) -> (f64, f64) {
+        let (u, v) = self.transform_difference(uv);
+        (u + self.offset.0, v + self.offset.1)
+    }
+
+    /// Difference between transformed coordinates, unaffected by the offset.
+    fn transform_difference(&self, (du, dv): (f64, f64)) -> (f64, f64) {
+        let (du, dv) = (du * self.scale.0, dv * self.scale.1);
+        let (sin, cos) = self.rotation.sin_cos();
+        (cos * du - sin * dv, sin * du + cos * dv)
+    }
+
+    /// Look the texture up at the coordinates `uv` before the transform,
+    /// changing by `dx` and `dy` from one pixel to the next.
+    fn lookup_at(&self, hit: &Hit, uv: (f64, f64), (dx, dy): ((f64, f64), (f64, f64))) -> Color {
+        let (u, v) = self.transform(uv);
+        let ((dudx, dvdx), (dudy, dvdy)) =
+            (self.transform_difference(dx), self.transform_difference(dy));
+        let footprint = Footprint {
+            dudx,
+            dvdx,
+            dudy,
+            dvdy,
+            ..hit.footprint
+        };
+        self.texture.value_at(&Hit {
+            u,
+            v,
+            footprint,
+            ..*hit
+        })
+    }
+
+    /// Coordinates of the projection of `p` for the mappings other than the
+    /// triplanar one.
+    fn project(&self, u: f64, v: f64, p: Point3) -> (f64, f64) {
+        match self.mapping {
+            Mapping::Uv | Mapping::Triplanar { .. } => (u, v),
+            Mapping::Planar { origin, u, v } => {
+                let d = p - origin;
+                (
+                    dot(d, u) / u.length_squared(),
+                    dot(d, v) / v.length_squared(),
+                )
+            }
+            Mapping::Cylindrical { center, axis } => {
+                let d = p - center;
+                let (x, y) = perpendicular_basis(axis);
+                let angle = f64::atan2(dot(d, y), dot(d, x));
+                (
+                    0.5 + angle / (2.0 * PI),
+                    dot(d, axis) / axis.length_squared(),
+                )
+            }
+            Mapping::Spherical { center } => sphere_uv(unit_vector(p - center)),
+        }
+    }
+}
+
+impl Texture for MappedTexture {
+    fn value(&self, u: f64, v: f64, p: Point3) -> Color {
+        match self.mapping {
+            // Without the normal, the projections are weighted equally.
+            Mapping::Triplanar { sharpness } => {
+                triplanar(vec3!(1.0, 1.0, 1.0), sharpness, |project| {
+                    let (u, v) = self.transform(project(p));
+                    self.texture.value(u, v, p)
+                })
+            }
+            _ => {
+                let (u, v) = self.transform(self.project(u, v, p));
+                self.texture.value(u, v, p)
+            }
+        }
+    }
+
+    fn value_at(&self, hit: &Hit) -> Color {
+        let Footprint { dpdx, dpdy, .. } = hit.footprint;
+        match self.mapping {
+            Mapping::Uv => {
+                let footprint = &hit.footprint;
+                let dx = (footprint.dudx, footprint.dvdx);
+                let dy = (footprint.dudy, footprint.dvdy);
+                self.lookup_at(hit, (hit.u, hit.v), (dx, dy))
+            }
+            Mapping::Triplanar { sharpness } => triplanar(hit.normal, sharpness, |project| {
+                self.lookup_at(hit, project(hit.p), (project(dpdx), project(dpdy)))
+            }),
+            Mapping::Planar { .. } | Mapping::Cylindrical { .. } | Mapping::Spherical { .. } => {
+                let uv = self.project(hit.u, hit.v, hit.p);
+                // Angles wrap around, the difference is the shortest way.
+                let periodic = !matches!(self.mapping, Mapping::Planar { .. });
+                let difference = |dp: Vec3| {
+                    let (u, v) = self.project(hit.u, hit.v, hit.p + dp);
+                    let du = u - uv.0;
+                    (if periodic { du - du.round() } else { du }, v - uv.1)
+                };
+                self.lookup_at(hit, uv, (difference(dpdx), difference(dpdy)))
+            }
+        }
+    }
+}
+
+/// Blend of `lookup` with the projections along the X, Y and Z axes,
+/// weighted by the matching components of `normal`.
+fn triplanar(
+    normal: Vec3,
+    sharpness: f64,
+    lookup: impl Fn(fn(Vec3) -> (f64, f64)) -> Color,
+) -> Color {
+    let weights = [normal.x(), normal.y(), normal.z()].map(|n| n.abs().powf(sharpness));
+    let total: f64 = weights.iter().sum();
+    let projections: [fn(Vec3) -> (f64, f64); 3] =
+        [|p| (p.z(), p.y()), |p| (p.x(), p.z()), |p| (p.x(), p.y())];
+    let mut color = Color::default();
+    for (weight, project) in weights.into_iter().zip(projections) {
+        if weight > 0.0 {
+            color += weight / total * lookup(project);
+        }
+    }
+    color
+}
+
+/// Two unit vectors perpendicular to `axis` and to each other.
+fn perpendicular_basis(axis: Vec3) -> (Vec3, Vec3) {
+    let w = unit_vector(axis);
+    let a = if w.x().abs() > 0.9 {
+        vec3!(0.0, 1.0, 0.0)
+    } else {
+        vec3!(1.0, 0.0, 0.0)
+    };
+    let x = unit_vector(cross(a, w));
+    (x, cross(w, x))
+}
@@ -0,0 +1,136 @@
+//! Conversion of the linear radiance of a film to displayable values.
+
+use crate::vec3::Color;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// Compression of the radiance range into `[0, 1]`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ToneMap {
+    /// Cut everything brighter than 1.
+    #[default]
+    Clamp,
+    /// Reinhard's `L / (1 + L)` on the luminance, keeping the hue.
+    Reinhard,
+    /// Narkowicz's fit of the ACES filmic curve.
+    Aces,
+}
+
+impl ToneMap {
+    pub const ALL: [ToneMap; 3] = [ToneMap::Clamp, ToneMap::Reinhard, ToneMap::Aces];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ToneMap::Clamp => "clamp",
+            ToneMap::Reinhard => "reinhard",
+            ToneMap::Aces => "aces",
+        }
+    }
+
+    pub fn apply(&self, color: Color) -> Color {
+        let mapped = match self {
+            ToneMap::Clamp => color,
+            ToneMap::Reinhard => {
+                let luminance = luminance(color);
+                if luminance > 0.0 {
+                    color * (1.0 / (1.0 + luminance))
+                } else {
+                    color
+                }
+            }
+            ToneMap::Aces => color!(aces(color.x()), aces(color.y()), aces(color.z())),
+        };
+        color!(
+            mapped.x().clamp(0.0, 1.0),
+            mapped.y().clamp(0.0, 1.0),
+            mapped.z().clamp(0.0, 1.0)
+        )
+    }
+}
+
+impl fmt::Display for ToneMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Debug)]
+pub struct UnknownToneMapError(String);
+
+impl fmt::Display for UnknownToneMapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names: Vec<_> = ToneMap::ALL.iter().map(ToneMap::name).collect();
+        write!(
+            f,
+            "unknown tone mapping `{}`, expected one of {}",
+            self.0,
+            names.join(", ")
+        )
+    }
+}
+
+impl Error for UnknownToneMapError {}
+
+impl FromStr for ToneMap {
+    type Err = UnknownToneMapError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ToneMap::ALL
+            .into_iter()
+            .find(|tone_map| tone_map.name() == s)
+            .ok_or_else(|| UnknownToneMapError(s.to_string()))
+    }
+}
+
+/// How the film is turned into an 8 bits image.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct OutputSettings {
+    /// Exposure compensation in stops, each one doubling the brightness.
+    pub exposure: f64,
+    pub tone_map: ToneMap,
+}
+
+impl OutputSettings {
+    /// Expose, tone map, and encode a linear color to sRGB bytes.
+    pub fn encode(&self, color: Color) -> [u8; 3] {
+        let color = self.tone_map.apply(2f64.powf(self.exposure) * color);
+        [
+            quantize(srgb_encode(color.x())),
+            quantize(srgb_encode(color.y())),
+            quantize(srgb_encode(color.z())),
+        ]
+    }
+}
+
+/// Relative luminance of a linear Rec. 709 color.
+pub fn luminance(color: Color) -> f64 {
+    0.2126 * color.x() + 0.7152 * color.y() + 0.0722 * color.z()
+}
+
+fn aces(x: f64) -> f64 {
+    (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14)
+}
+
+/// The sRGB transfer function, from linear to encoded values.
+pub fn srgb_encode(linear: f64) -> f64 {
+    if linear <= 0.003_130_8 {
+        12.92 * linear
+    } else {
+        1.055 * linear.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Inverse of `srgb_encode`.
+pub fn srgb_decode(encoded: f64) -> f64 {
+    if encoded <= 0.040_45 {
+        encoded / 12.92
+    } else {
+        ((encoded + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+pub(crate) fn quantize(value: f64) -> u8 {
+    // NaN ends up as 0 through the saturating conversion.
+    (255.0 * value.clamp(0.0, 1.0) + 0.5) as u8
+}
@@ -0,0 +1,286 @@
+use crate::aabb::{Aabb, Interval};
+use crate::vec3::{unit_vector, Point3, Vec3};
+use std::ops::Mul;
+
+/// A row-major 4x4 matrix acting on homogeneous coordinates.
+#[derive(Copy, Clone, PartialEq)]
+pub struct Matrix4 {
+    pub m: [[f64; 4]; 4],
+}
+
+impl Matrix4 {
+    pub const fn identity() -> Self {
+        Matrix4 {
+            m: [
+                [1.0, 0.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+        }
+    }
+
+    pub fn translation(offset: Vec3) -> Self {
+        let mut result = Self::identity();
+        for i in 0..3 {
+            result.m[i][3] = offset[i];
+        }
+        result
+    }
+
+    pub fn scaling(factors: Vec3) -> Self {
+        let mut result = Self::identity();
+        for i in 0..3 {
+            result.m[i][i] = factors[i];
+        }
+        result
+    }
+
+    /// Counterclockwise rotation around `axis`, angle in degrees.
+    pub fn rotation(axis: Vec3, angle: f64) -> Self {
+        let a = unit_vector(axis);
+        let (sin, cos) = angle.to_radians().sin_cos();
+        let t = 1.0 - cos;
+        let (x, y, z) = (a.x(), a.y(), a.z());
+        Matrix4 {
+            m: [
+                [
+                    t * x * x + cos,
+                    t * x * y - sin * z,
+                    t * x * z + sin * y,
+                    0.0,
+                ],
+                [
+                    t * x * y + sin * z,
+                    t * y * y + cos,
+                    t * y * z - sin * x,
+                    0.0,
+                ],
+                [
+                    t * x * z - sin * y,
+                    t * y * z + sin * x,
+                    t * z * z + cos,
+                    0.0,
+                ],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+        }
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut result = *self;
+        for i in 0..4 {
+            for j in 0..4 {
+                result.m[i][j] = self.m[j][i];
+            }
+        }
+        result
+    }
+
+    /// Invert the matrix using Gauss-Jordan elimination, `None` if it is
+    /// singular.
+    pub fn inverse(&self) -> Option<Self> {
+        let mut a = self.m;
+        let mut inv = Self::identity().m;
+
+        for column in 0..4 {
+            let pivot = (column..4)
+                .max_by(|&i, &j| a[i][column].abs().total_cmp(&a[j][column].abs()))
+                .unwrap();
+            if a[pivot][column].abs() < 1e-12 {
+                return None;
+            }
+            a.swap(column, pivot);
+            inv.swap(column, pivot);
+
+            let scale = 1.0 / a[column][column];
+            for j in 0..4 {
+                a[column][j] *= scale;
+                inv[column][j] *= scale;
+            }
+
+            for row in 0..4 {
+                if row == column {
+                    continue;
+                }
+                let factor = a[row][column];
+                for j in 0..4 {
+                    a[row][j] -= factor * a[column][j];
+                    inv[row][j] -= factor * inv[column][j];
+                }
+            }
+        }
+
+        Some(Matrix4 { m: inv })
+    }
+
+    pub fn transform_point(&self, p: Point3) -> Point3 {
+        let m = &self.m;
+        let x = m[0][0] * p.x() + m[0][1] * p.y() + m[0][2] * p.z() + m[0][3];
+        let y = m[1][0] * p.x() + m[1][1] * p.y() + m[1][2] * p.z() + m[1][3];
+        let z = m[2][0] * p.x() + m[2][1] * p.y() + m[2][2] * p.z() + m[2][3];
+        let w = m[3][0] * p.x() + m[3][1] * p.y() + m[3][2] * p.z() + m[3][3];
+        if w == 1.0 {
+            point!(x, y, z)
+        } else {
+            point!(x / w, y / w, z / w)
+        }
+    }
+
+    pub fn transform_vector(&self, v: Vec3) -> Vec3 {
+        let m = &self.m;
+        vec3!(
+            m[0][0] * v.x() + m[0][1] * v.y() + m[0][2] * v.z(),
+            m[1][0] * v.x() + m[1][1] * v.y() + m[1][2] * v.z(),
+            m[2][0] * v.x() + m[2][1] * v.y() + m[2][2] * v.z()
+        )
+    }
+}
+
+impl Default for Matrix4 {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Mul for Matrix4 {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self::Output {
+        let mut result = Matrix4 { m: [[0.0; 4]; 4] };
+        for i in 0..4 {
+            for j in 0..4 {
+                result.m[i][j] = (0..4).map(|k| self.m[i][k] * other.m[k][j]).sum();
+            }
+        }
+        result
+    }
+}
+
+/// An invertible affine transformation, keeping its inverse around.
+#[derive(Copy, Clone, Default)]
+pub struct Transform {
+    matrix: Matrix4,
+    inverse: Matrix4,
+}
+
+impl Transform {
+    /// Create a transform from its matrix, `None` if it cannot be inverted.
+    pub fn new(matrix: Matrix4) -> Option<Self> {
+        matrix
+            .inverse()
+            .map(|inverse| Transform { matrix, inverse })
+    }
+
+    pub fn identity() -> Self {
+        Self::default()
+    }
+
+    pub fn translate(offset: Vec3) -> Self {
+        Transform {
+            matrix: Matrix4::translation(offset),
+            inverse: Matrix4::translation(-offset),
+        }
+    }
+
+    /// Scale along each axis.
+    ///
+    /// # Panics
+    ///
+    /// Panics if one of the factors is 0.
+    pub fn scale(factors: Vec3) -> Self {
+        assert!(
+            factors.x() != 0.0 && factors.y() != 0.0 && factors.z() != 0.0,
+            "scale factors must not be 0"
+        );
+        Transform {
+            matrix: Matrix4::scaling(factors),
+            inverse: Matrix4::scaling(vec3!(
+                1.0 / factors.x(),
+                1.0 / factors.y(),
+                1.0 / factors.z()
+            )),
+        }
+    }
+
+    /// Counterclockwise rotation around `axis`, angle in degrees.
+    pub fn rotate(axis: Vec3, angle: f64) -> Self {
+        let matrix = Matrix4::rotation(axis, angle);
+        Transform {
+            matrix,
+            inverse: matrix.transpose(),
+        }
+    }
+
+    pub fn rotate_x(angle: f64) -> Self {
+        Self::rotate(vec3!(1.0, 0.0, 0.0), angle)
+    }
+
+    pub fn rotate_y(angle: f64) -> Self {
+        Self::rotate(vec3!(0.0, 1.0, 0.0), angle)
+    }
+
+    pub fn rotate_z(angle: f64) -> Self {
+        Self::rotate(vec3!(0.0, 0.0, 1.0), angle)
+    }
+
+    /// The transform applying `self` first and `next` afterwards.
+    pub fn then(&self, next: &Transform) -> Self {
+        Transform {
+            matrix: next.matrix * self.matrix,
+            inverse: self.inverse * next.inverse,
+        }
+    }
+
+    pub fn inverse(&self) -> Self {
+        Transform {
+            matrix: self.inverse,
+            inverse: self.matrix,
+        }
+    }
+
+    pub fn matrix(&self) -> &Matrix4 {
+        &self.matrix
+    }
+
+    pub fn point(&self, p: Point3) -> Point3 {
+        self.matrix.transform_point(p)
+    }
+
+    pub fn vector(&self, v: Vec3) -> Vec3 {
+        self.matrix.transform_vector(v)
+    }
+
+    /// Transform a surface normal, which uses the inverse transpose so that it
+    /// stays perpendicular to the transformed surface.
+    pub fn normal(&self, n: Vec3) -> Vec3 {
+        unit_vector(self.inverse.transpose().transform_vector(n))
+    }
+
+    /// Box around the transformed corners of `bbox`.
+    pub fn bounding_box(&self, bbox: &Aabb) -> Aabb {
+        let mut minimum = point!(f64::INFINITY, f64::INFINITY, f64::INFINITY);
+        let mut maximum = point!(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for corner in 0..8 {
+            let pick = |axis: usize| {
+                let interval = bbox.axis(axis);
+                if corner & (1 << axis) == 0 {
+                    interval.minimum
+                } else {
+                    interval.maximum
+                }
+            };
+            let p = self.point(point!(pick(0), pick(1), pick(2)));
+            for axis in 0..3 {
+                minimum[axis] = minimum[axis].min(p[axis]);
+                maximum[axis] = maximum[axis].max(p[axis]);
+            }
+        }
+
+        Aabb::new(
+            Interval::new(minimum.x(), maximum.x()),
+            Interval::new(minimum.y(), maximum.y()),
+            Interval::new(minimum.z(), maximum.z()),
+        )
+    }
+}
@@ -1,5 +1,6 @@
 use rand::distributions::uniform::SampleUniform;
 use rand::distributions::{Distribution, Uniform};
+use rand::Rng;
 use std::ops::RangeInclusive;
 
 pub fn clamp<T>(input: T, min: T, max: T) -> T
@@ -15,11 +16,24 @@ where
     }
 }
 
-pub fn random<T>(r: RangeInclusive<T>) -> T
+/// Draw a value in `r` from `rng`.
+pub fn random_from<T>(rng: &mut impl Rng, r: RangeInclusive<T>) -> T
 where
     T: SampleUniform,
 {
-    let mut rng = rand::thread_rng();
-    let dist = Uniform::from(r);
-    dist.sample(&mut rng)
+    Uniform::from(r).sample(rng)
+}
+
+/// Seed of the independent stream used for one sample of one pixel, so that a
+/// render does not depend on the order in which the threads pick the pixels.
+pub fn stream_seed(seed: u64, pixel: u64, sample: u64) -> u64 {
+    mix(mix(mix(seed) ^ pixel) ^ sample)
+}
+
+/// SplitMix64 finalizer, spreading every input bit over the whole output.
+fn mix(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
 }
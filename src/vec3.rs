@@ -1,8 +1,12 @@
 use rand::distributions::{Distribution, Uniform};
+use rand::Rng;
+use std::error::Error;
 use std::fmt;
+use std::num::ParseFloatError;
 use std::ops::{
     Add, AddAssign, Div, DivAssign, Index, IndexMut, Mul, MulAssign, Neg, RangeInclusive, Sub,
 };
+use std::str::FromStr;
 
 pub type Point3 = Vec3;
 pub type Color = Vec3;
@@ -10,21 +14,21 @@ pub type Color = Vec3;
 #[macro_export]
 macro_rules! color {
     ($x:expr, $y:expr,$z:expr) => {
-        crate::vec3::Color::new($x, $y, $z)
+        $crate::vec3::Color::new($x, $y, $z)
     };
 }
 
 #[macro_export]
 macro_rules! point {
     ($x:expr, $y:expr,$z:expr) => {
-        crate::vec3::Point3::new($x, $y, $z)
+        $crate::vec3::Point3::new($x, $y, $z)
     };
 }
 
 #[macro_export]
 macro_rules! vec3 {
     ($x:expr, $y:expr,$z:expr) => {
-        crate::vec3::Vec3::new($x, $y, $z)
+        $crate::vec3::Vec3::new($x, $y, $z)
     };
 }
 
@@ -34,39 +38,27 @@ pub struct Vec3 {
 }
 
 impl Vec3 {
-    pub fn new(x: f64, y: f64, z: f64) -> Self {
+    pub const fn new(x: f64, y: f64, z: f64) -> Self {
         Vec3 { e: [x, y, z] }
     }
 
-    pub fn random() -> Self {
-        let mut rng = rand::thread_rng();
-        let dist = Uniform::from(0.0..=1.0);
-        Vec3 {
-            e: [
-                dist.sample(&mut rng),
-                dist.sample(&mut rng),
-                dist.sample(&mut rng),
-            ],
-        }
+    /// A vector with components drawn in `[0, 1]` from `rng`.
+    pub fn random_from(rng: &mut impl Rng) -> Self {
+        Vec3::delimited_from(rng, 0.0..=1.0)
     }
 
-    pub fn delimited(range: RangeInclusive<f64>) -> Self {
-        let mut rng = rand::thread_rng();
+    /// A vector with components drawn in `range` from `rng`.
+    pub fn delimited_from(rng: &mut impl Rng, range: RangeInclusive<f64>) -> Self {
         let dist = Uniform::from(range);
         Vec3 {
-            e: [
-                dist.sample(&mut rng),
-                dist.sample(&mut rng),
-                dist.sample(&mut rng),
-            ],
+            e: [dist.sample(rng), dist.sample(rng), dist.sample(rng)],
         }
     }
 
-    pub fn random_in_unit_disk() -> Self {
-        let mut rng = rand::thread_rng();
+    pub fn random_in_unit_disk(rng: &mut impl Rng) -> Self {
         let dist = Uniform::from(-1.0..=1.0);
         loop {
-            let p = Vec3::new(dist.sample(&mut rng), dist.sample(&mut rng), 0.0);
+            let p = Vec3::new(dist.sample(rng), dist.sample(rng), 0.0);
             if p.length_squared() >= 1.0 {
                 continue;
             }
@@ -75,9 +67,9 @@ impl Vec3 {
     }
 
     /// A (bad) diffuse renderer
-    pub fn random_in_unit_sphere() -> Self {
+    pub fn random_in_unit_sphere(rng: &mut impl Rng) -> Self {
         loop {
-            let p = Vec3::delimited(-1.0..=1.0);
+            let p = Vec3::delimited_from(rng, -1.0..=1.0);
             if p.length_squared() >= 1.0 {
                 continue;
             }
@@ -86,8 +78,8 @@ impl Vec3 {
     }
 
     /// Intuitive diffuse renderer
-    pub fn random_in_hemisphere(normal: Vec3) -> Self {
-        let in_unit_sphere = Self::random_in_unit_sphere();
+    pub fn random_in_hemisphere(rng: &mut impl Rng, normal: Vec3) -> Self {
+        let in_unit_sphere = Self::random_in_unit_sphere(rng);
         if dot(in_unit_sphere, normal) > 0.0 {
             in_unit_sphere
         } else {
@@ -96,8 +88,8 @@ impl Vec3 {
     }
 
     /// Accurate diffuse renderer.
-    pub fn random_unit() -> Self {
-        unit_vector(Self::random_in_unit_sphere())
+    pub fn random_unit(rng: &mut impl Rng) -> Self {
+        unit_vector(Self::random_in_unit_sphere(rng))
     }
 
     pub fn length_squared(&self) -> f64 {
@@ -126,6 +118,12 @@ impl Vec3 {
     }
 }
 
+impl From<[f64; 3]> for Vec3 {
+    fn from(e: [f64; 3]) -> Self {
+        Vec3 { e }
+    }
+}
+
 impl Neg for Vec3 {
     type Output = Self;
     fn neg(self) -> Self {
@@ -271,3 +269,40 @@ impl fmt::Display for Vec3 {
         write!(f, "{} {} {}", self.e[0], self.e[1], self.e[2])
     }
 }
+
+#[derive(Debug)]
+pub enum ParseVec3Error {
+    ComponentCount(usize),
+    Component(ParseFloatError),
+}
+
+impl fmt::Display for ParseVec3Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseVec3Error::ComponentCount(count) => {
+                write!(f, "expected 3 comma separated components, found {}", count)
+            }
+            ParseVec3Error::Component(err) => write!(f, "invalid component: {}", err),
+        }
+    }
+}
+
+impl Error for ParseVec3Error {}
+
+/// Parse a vector written as `x,y,z`.
+impl FromStr for Vec3 {
+    type Err = ParseVec3Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let components = s
+            .split(',')
+            .map(|c| c.trim().parse::<f64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(ParseVec3Error::Component)?;
+
+        match components[..] {
+            [x, y, z] => Ok(Vec3::new(x, y, z)),
+            _ => Err(ParseVec3Error::ComponentCount(components.len())),
+        }
+    }
+}
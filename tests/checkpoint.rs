@@ -0,0 +1,160 @@
+use raytracer::render::progress::Silent;
+use raytracer::render::progressive::CheckpointError;
+use raytracer::scene;
+use raytracer::{
+    render_progressive, Accumulation, AdaptiveSampling, Aov, CancelToken, Denoiser, Film,
+    LinearBvh, Progressive, RenderSettings, SettingsError,
+};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+const SIZE: usize = 24;
+const SCENE: &str = "cornell_box";
+
+fn render_settings(denoiser: Denoiser) -> RenderSettings {
+    let mut settings = RenderSettings::new(SIZE, 1.0);
+    settings.sample_per_pixel = 4;
+    settings.max_depth = 8;
+    settings.denoiser = denoiser;
+    settings
+}
+
+fn render_cornell_box(settings: &RenderSettings, aovs: &[Aov]) -> Accumulation {
+    let entry = scene::find(SCENE).unwrap();
+    let world = LinearBvh::new((entry.build)(settings.seed));
+    let camera = (entry.camera)().aspect_ratio(1.0).build();
+    let mut accumulation = Accumulation::new(settings, aovs);
+    render_progressive(
+        &world,
+        &(entry.environment)(),
+        &camera,
+        settings,
+        &mut accumulation,
+        &Silent,
+        &CancelToken::new(),
+        |_| Ok::<_, SettingsError>(()),
+    )
+    .unwrap();
+    accumulation
+}
+
+/// A path in the temporary directory, unique to this test process.
+fn temporary_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("raytracer-{}-{}", std::process::id(), name))
+}
+
+fn assert_same_film(actual: &Film, expected: &Film, name: &str) {
+    assert_eq!(
+        (actual.width(), actual.height()),
+        (expected.width(), expected.height())
+    );
+    assert!(
+        actual.pixels() == expected.pixels(),
+        "{} differs after the round trip",
+        name
+    );
+}
+
+#[test]
+fn round_trip_keeps_film_and_aovs() {
+    let settings = render_settings(Denoiser::ATrous);
+    let aovs = [Aov::Normal, Aov::Depth, Aov::MaterialId, Aov::Alpha];
+    let accumulation = render_cornell_box(&settings, &aovs);
+    let path = temporary_path("round-trip.ckpt");
+
+    accumulation
+        .save_checkpoint(&path, &settings, SCENE)
+        .unwrap();
+    let loaded = Accumulation::load_checkpoint(&path, &settings, SCENE, &aovs);
+    fs::remove_file(&path).unwrap();
+    let loaded = loaded.unwrap();
+
+    assert_eq!(loaded.samples(), accumulation.samples());
+    assert_eq!(loaded.sample_counts(), accumulation.sample_counts());
+    let (film, aov_films) = accumulation.finish(&settings);
+    let (loaded_film, loaded_aov_films) = loaded.finish(&settings);
+    assert_same_film(&loaded_film, &film, "denoised film");
+    for aov in aovs {
+        assert_same_film(
+            loaded_aov_films.get(aov).unwrap(),
+            aov_films.get(aov).unwrap(),
+            aov.name(),
+        );
+    }
+}
+
+#[test]
+fn checkpoint_without_aovs_cannot_resume_with_them() {
+    let settings = render_settings(Denoiser::Off);
+    let accumulation = render_cornell_box(&settings, &[]);
+    let path = temporary_path("without-aovs.ckpt");
+
+    accumulation
+        .save_checkpoint(&path, &settings, SCENE)
+        .unwrap();
+    let with_aov = Accumulation::load_checkpoint(&path, &settings, SCENE, &[Aov::Normal]);
+    let with_denoiser =
+        Accumulation::load_checkpoint(&path, &render_settings(Denoiser::ATrous), SCENE, &[]);
+    let without = Accumulation::load_checkpoint(&path, &settings, SCENE, &[]);
+    fs::remove_file(&path).unwrap();
+
+    assert!(matches!(with_aov, Err(CheckpointError::Mismatch(_))));
+    assert!(matches!(with_denoiser, Err(CheckpointError::Mismatch(_))));
+    assert_same_film(&without.unwrap().film(), &accumulation.film(), "film");
+}
+
+#[test]
+fn checkpoint_of_another_render_is_rejected() {
+    let settings = render_settings(Denoiser::Off);
+    let accumulation = render_cornell_box(&settings, &[]);
+    let path = temporary_path("other-render.ckpt");
+
+    accumulation
+        .save_checkpoint(&path, &settings, SCENE)
+        .unwrap();
+    let mut other_seed = settings;
+    other_seed.seed += 1;
+    let mut other_size = settings;
+    other_size.resize(2 * SIZE, 1.0);
+    let mut other_samples = settings;
+    other_samples.sample_per_pixel += 1;
+    let mut other_depth = settings;
+    other_depth.max_depth += 1;
+    let mut adaptive = settings;
+    adaptive.adaptive = Some(AdaptiveSampling::new(0.05, 16));
+    let results = [
+        Accumulation::load_checkpoint(&path, &other_seed, SCENE, &[]),
+        Accumulation::load_checkpoint(&path, &other_size, SCENE, &[]),
+        Accumulation::load_checkpoint(&path, &other_samples, SCENE, &[]),
+        Accumulation::load_checkpoint(&path, &other_depth, SCENE, &[]),
+        Accumulation::load_checkpoint(&path, &adaptive, SCENE, &[]),
+        Accumulation::load_checkpoint(&path, &settings, "two_sphere", &[]),
+    ];
+    fs::remove_file(&path).unwrap();
+
+    for result in results {
+        assert!(matches!(result, Err(CheckpointError::Mismatch(_))));
+    }
+}
+
+#[test]
+fn scheduling_settings_may_change_on_resume() {
+    let settings = render_settings(Denoiser::Off);
+    let accumulation = render_cornell_box(&settings, &[]);
+    let path = temporary_path("scheduling.ckpt");
+
+    accumulation
+        .save_checkpoint(&path, &settings, SCENE)
+        .unwrap();
+    let mut scheduling = settings;
+    scheduling.tile_size = 7;
+    scheduling.progressive = Some(Progressive {
+        pass_samples: 1,
+        time_budget: None,
+    });
+    let loaded = Accumulation::load_checkpoint(&path, &scheduling, SCENE, &[]);
+    fs::remove_file(&path).unwrap();
+
+    assert_same_film(&loaded.unwrap().film(), &accumulation.film(), "film");
+}
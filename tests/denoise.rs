@@ -0,0 +1,85 @@
+use raytracer::denoise::Guides;
+use raytracer::scene;
+use raytracer::{render, render_with_aovs, Aov, Denoiser, Film, LinearBvh, RenderSettings};
+
+const SIZE: usize = 32;
+
+fn render_cornell_box(samples: usize, denoiser: Denoiser) -> Film {
+    let entry = scene::find("cornell_box").unwrap();
+    let mut settings = RenderSettings::new(SIZE, 1.0);
+    settings.sample_per_pixel = samples;
+    settings.max_depth = 8;
+    settings.denoiser = denoiser;
+    let world = LinearBvh::new((entry.build)(settings.seed));
+    let camera = (entry.camera)().aspect_ratio(1.0).build();
+    render(&world, &(entry.environment)(), &camera, &settings).unwrap()
+}
+
+fn rmse(film: &Film, reference: &Film) -> f64 {
+    let squares: f64 = film
+        .pixels()
+        .iter()
+        .flatten()
+        .zip(reference.pixels().iter().flatten())
+        .map(|(&a, &b)| (a as f64 - b as f64).powi(2))
+        .sum();
+    (squares / (3 * film.pixels().len()) as f64).sqrt()
+}
+
+#[test]
+fn a_trous_reduces_error_against_reference() {
+    let reference = render_cornell_box(1024, Denoiser::Off);
+    let noisy = render_cornell_box(16, Denoiser::Off);
+    let denoised = render_cornell_box(16, Denoiser::ATrous);
+
+    let (noisy_error, denoised_error) = (rmse(&noisy, &reference), rmse(&denoised, &reference));
+    assert!(
+        denoised_error < 0.6 * noisy_error,
+        "denoised error {} not clearly below noisy error {}",
+        denoised_error,
+        noisy_error
+    );
+}
+
+#[test]
+fn a_trous_keeps_uniform_film() {
+    let (width, height) = (16, 8);
+    let uniform = |value: [f32; 3]| Film::from_raw(width, height, vec![value; width * height]);
+    let film = uniform([0.25, 0.5, 0.75]);
+    let albedo = uniform([0.5, 0.5, 0.5]);
+    let normal = uniform([0.0, 0.0, 1.0]);
+    let depth = uniform([2.0; 3]);
+    let guides = Guides {
+        albedo: &albedo,
+        normal: &normal,
+        depth: &depth,
+    };
+
+    let denoised = Denoiser::ATrous.apply(&film, &guides);
+    for (a, b) in denoised.pixels().iter().zip(film.pixels()) {
+        for c in 0..3 {
+            assert!((a[c] - b[c]).abs() < 1e-5, "{:?} != {:?}", a, b);
+        }
+    }
+}
+
+#[test]
+fn denoiser_leaves_requested_aovs_only() {
+    let entry = scene::find("cornell_box").unwrap();
+    let mut settings = RenderSettings::new(8, 1.0);
+    settings.sample_per_pixel = 1;
+    settings.denoiser = Denoiser::ATrous;
+    let world = LinearBvh::new((entry.build)(settings.seed));
+    let camera = (entry.camera)().aspect_ratio(1.0).build();
+
+    let (_, aovs) = render_with_aovs(
+        &world,
+        &(entry.environment)(),
+        &camera,
+        &settings,
+        &[Aov::Alpha],
+    )
+    .unwrap();
+    let rendered: Vec<_> = aovs.iter().map(|(aov, _)| aov).collect();
+    assert_eq!(rendered, [Aov::Alpha]);
+}
@@ -0,0 +1,135 @@
+use exr::prelude as openexr;
+use raytracer::film::{self, ExrPrecision};
+use raytracer::hdr::HdrImage;
+use raytracer::Film;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+const WIDTH: usize = 13;
+const HEIGHT: usize = 4;
+
+/// A film whose pixels all differ, with values over several decades.
+fn film(offset: f32) -> Film {
+    let data = (0..WIDTH * HEIGHT)
+        .map(|i| {
+            let value = i as f32 / 7.0 + offset;
+            [value, 0.01 * value, 100.0 * value]
+        })
+        .collect();
+    Film::from_raw(WIDTH, HEIGHT, data)
+}
+
+/// A path in the temporary directory, unique to this test process.
+fn temporary_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("raytracer-{}-{}", std::process::id(), name))
+}
+
+/// Read the channels of an OpenEXR file by name.
+fn read_exr(path: &PathBuf) -> HashMap<String, Vec<f32>> {
+    let image = openexr::read_all_flat_layers_from_file(path).unwrap();
+    assert_eq!(image.layer_data.len(), 1);
+    let layer = &image.layer_data[0];
+    assert_eq!((layer.size.width(), layer.size.height()), (WIDTH, HEIGHT));
+    layer
+        .channel_data
+        .list
+        .iter()
+        .map(|channel| {
+            (
+                channel.name.to_string(),
+                channel.sample_data.values_as_f32().collect(),
+            )
+        })
+        .collect()
+}
+
+fn channel(film: &Film, index: usize) -> Vec<f32> {
+    film.pixels().iter().map(|pixel| pixel[index]).collect()
+}
+
+#[test]
+fn pfm_round_trip() {
+    let film = film(0.5);
+    let mut file = vec![];
+    film.write_pfm(&mut file).unwrap();
+
+    let header = format!("PF\n{} {}\n-1.0\n", WIDTH, HEIGHT);
+    assert!(file.starts_with(header.as_bytes()));
+    let values: Vec<f32> = file[header.len()..]
+        .chunks_exact(4)
+        .map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap()))
+        .collect();
+    // Rows are stored from the bottom up.
+    let rows: Vec<&[f32]> = values.chunks(3 * WIDTH).rev().collect();
+    assert_eq!(rows.concat(), film.pixels().concat());
+}
+
+#[test]
+fn hdr_round_trip() {
+    let film = film(0.5);
+    let mut file = vec![];
+    film.write_hdr(&mut file).unwrap();
+
+    let image = HdrImage::read(file.as_slice()).unwrap();
+    assert_eq!((image.width(), image.height()), (WIDTH, HEIGHT));
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            let (actual, expected) = (image.pixel(x, y), film.pixel(x, y));
+            // 8 bits of mantissa relative to the largest component.
+            let tolerance = expected.z() / 128.0;
+            for (a, e) in [
+                (actual.x(), expected.x()),
+                (actual.y(), expected.y()),
+                (actual.z(), expected.z()),
+            ] {
+                assert!((a - e).abs() <= tolerance, "({}, {}): {} != {}", x, y, a, e);
+            }
+        }
+    }
+}
+
+#[test]
+fn exr_layers_round_trip() {
+    let (beauty, albedo) = (film(0.5), film(2.0));
+    let path = temporary_path("layers.exr");
+    film::save_exr_layers(
+        &path,
+        &[("", &beauty), ("albedo", &albedo)],
+        ExrPrecision::Float,
+    )
+    .unwrap();
+    let channels = read_exr(&path);
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(channels.len(), 6);
+    for (index, name) in ["R", "G", "B"].into_iter().enumerate() {
+        assert_eq!(channels[name], channel(&beauty, index));
+        assert_eq!(
+            channels[&format!("albedo.{}", name)],
+            channel(&albedo, index)
+        );
+    }
+}
+
+#[test]
+fn exr_half_round_trip() {
+    let film = film(0.5);
+    let path = temporary_path("half.exr");
+    film.save_exr(&path, ExrPrecision::Half).unwrap();
+    let channels = read_exr(&path);
+    fs::remove_file(&path).unwrap();
+
+    for (index, name) in ["R", "G", "B"].into_iter().enumerate() {
+        for (actual, expected) in channels[name].iter().zip(channel(&film, index)) {
+            // 11 bits of mantissa.
+            assert!(
+                (actual - expected).abs() <= expected.abs() / 1024.0,
+                "{} != {}",
+                actual,
+                expected
+            );
+        }
+    }
+}
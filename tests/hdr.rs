@@ -0,0 +1,84 @@
+use raytracer::hdr::{self, HdrError, HdrImage};
+
+/// Pixels with runs of equal values and varying ones, over several decades.
+fn pixels(width: usize, height: usize) -> Vec<[f32; 3]> {
+    (0..width * height)
+        .map(|i| {
+            let (x, y) = (i % width, i / width);
+            if x < width / 2 {
+                [0.25, 0.5, 1.0]
+            } else {
+                let value = (x * y) as f32 / 3.0 + 0.01;
+                [value, 2.0 * value, 100.0 * value]
+            }
+        })
+        .collect()
+}
+
+fn round_trip(width: usize, height: usize) {
+    let pixels = pixels(width, height);
+    let mut file = vec![];
+    hdr::write_rgbe(&mut file, width, height, &pixels).unwrap();
+
+    let image = HdrImage::read(file.as_slice()).unwrap();
+
+    assert_eq!((image.width(), image.height()), (width, height));
+    for (i, expected) in pixels.iter().enumerate() {
+        let actual = image.pixel(i % width, i / width);
+        // The shared exponent keeps 8 bits relative to the largest component.
+        let tolerance = expected.iter().fold(0f32, |max, &c| max.max(c)) as f64 / 128.0;
+        for (a, e) in [actual.x(), actual.y(), actual.z()].iter().zip(expected) {
+            assert!(
+                (a - *e as f64).abs() <= tolerance,
+                "pixel {}: expected {:?}, found {}",
+                i,
+                expected,
+                a
+            );
+        }
+    }
+}
+
+fn read_header(resolution: &str) -> Result<HdrImage, HdrError> {
+    let file = format!("#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n{}\n", resolution);
+    HdrImage::read(file.as_bytes())
+}
+
+fn assert_format_error(result: Result<HdrImage, HdrError>, message: &str) {
+    match result {
+        Err(HdrError::Format(actual)) => assert!(actual.contains(message), "{}", actual),
+        Err(err) => panic!("expected a format error, found {}", err),
+        Ok(_) => panic!("expected a format error"),
+    }
+}
+
+#[test]
+fn run_length_encoded_round_trip() {
+    round_trip(37, 5);
+}
+
+#[test]
+fn flat_round_trip() {
+    // Scanlines narrower than 8 pixels cannot be run length encoded.
+    round_trip(5, 3);
+}
+
+#[test]
+fn malformed_headers_are_rejected() {
+    assert_format_error(
+        HdrImage::read("P3\n2 2\n".as_bytes()),
+        "missing `#?` signature",
+    );
+    assert_format_error(
+        HdrImage::read("#?RADIANCE\nFORMAT=32-bit_rle_xyze\n\n".as_bytes()),
+        "unsupported format",
+    );
+    assert_format_error(read_header("+Y 2 +X 2"), "unsupported resolution line");
+    assert_format_error(read_header("-Y 2 +X 0"), "empty");
+    assert_format_error(read_header("-Y 0 +X 2"), "empty");
+    assert_format_error(read_header("-Y 100000 +X 100000"), "too large");
+    assert_format_error(
+        read_header("-Y 99999999999999999999 +X 2"),
+        "unsupported resolution line",
+    );
+}
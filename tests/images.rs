@@ -0,0 +1,80 @@
+use raytracer::image::writer::{AsciiPpmWriter, BinaryPpmWriter, ImageWriter, PngWriter};
+use raytracer::image::ImageFormat;
+use raytracer::Image;
+
+const WIDTH: usize = 13;
+const HEIGHT: usize = 4;
+
+fn image() -> Image {
+    let data = (0..WIDTH * HEIGHT * 3)
+        .map(|i| (i * 37 % 256) as u8)
+        .collect();
+    Image::from_raw(WIDTH, HEIGHT, data)
+}
+
+#[test]
+fn png_round_trip() {
+    let image = image();
+    let mut file = vec![];
+    PngWriter.write(&image, &mut file).unwrap();
+
+    let mut reader = png::Decoder::new(file.as_slice()).read_info().unwrap();
+    let mut data = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut data).unwrap();
+    assert_eq!((info.width, info.height), (WIDTH as u32, HEIGHT as u32));
+    assert_eq!(
+        (info.color_type, info.bit_depth),
+        (png::ColorType::Rgb, png::BitDepth::Eight)
+    );
+    assert_eq!(&data[..info.buffer_size()], image.as_bytes());
+}
+
+#[test]
+fn binary_ppm_round_trip() {
+    let image = image();
+    let mut file = vec![];
+    BinaryPpmWriter.write(&image, &mut file).unwrap();
+
+    let header = format!("P6\n{} {}\n255\n", WIDTH, HEIGHT);
+    assert!(file.starts_with(header.as_bytes()));
+    assert_eq!(&file[header.len()..], image.as_bytes());
+}
+
+#[test]
+fn ascii_ppm_round_trip() {
+    let image = image();
+    let mut file = vec![];
+    AsciiPpmWriter.write(&image, &mut file).unwrap();
+
+    let text = String::from_utf8(file).unwrap();
+    let mut lines = text.lines();
+    assert_eq!(lines.next(), Some("P3"));
+    assert_eq!(lines.next(), Some(format!("{} {}", WIDTH, HEIGHT).as_str()));
+    assert_eq!(lines.next(), Some("255"));
+    let samples: Vec<u8> = lines
+        .flat_map(|line| line.split_whitespace())
+        .map(|sample| sample.parse().unwrap())
+        .collect();
+    assert_eq!(samples, image.as_bytes());
+}
+
+#[test]
+fn formats_select_their_writer() {
+    let image = image();
+    let signatures: [(ImageFormat, &[u8]); 3] = [
+        (ImageFormat::Png, b"\x89PNG"),
+        (ImageFormat::PpmAscii, b"P3\n"),
+        (ImageFormat::Ppm, b"P6\n"),
+    ];
+
+    for (format, signature) in signatures {
+        assert_eq!(format.name().parse::<ImageFormat>().unwrap(), format);
+        let mut file = vec![];
+        format.writer().write(&image, &mut file).unwrap();
+        assert!(file.starts_with(signature), "{}", format);
+    }
+    assert_eq!(ImageFormat::from_extension("PNG"), Some(ImageFormat::Png));
+    assert_eq!(ImageFormat::from_extension("ppm"), Some(ImageFormat::Ppm));
+    assert_eq!(ImageFormat::from_extension("exr"), None);
+    assert!("pgm".parse::<ImageFormat>().is_err());
+}
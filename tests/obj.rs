@@ -0,0 +1,181 @@
+use raytracer::material::lambertian::Lambertian;
+use raytracer::obj::{self, ObjError};
+use raytracer::sampler::independent::Independent;
+use raytracer::vec3::dot;
+use raytracer::{Color, Hit, Hittable, Interval, Material, Point3, Ray, Vec3};
+use std::path::Path;
+use std::sync::Arc;
+
+fn parse(source: &str) -> Result<obj::Model, ObjError> {
+    let default_material: Arc<dyn Material> = Arc::new(Lambertian::new(color(0.5, 0.5, 0.5)));
+    obj::parse(source, Path::new("test.obj"), default_material)
+}
+
+fn color(r: f64, g: f64, b: f64) -> Color {
+    Color::from([r, g, b])
+}
+
+fn assert_near(actual: Vec3, expected: Vec3) {
+    assert!(
+        (actual - expected).near_zero(),
+        "expected ({}, {}, {}), found ({}, {}, {})",
+        expected.x(),
+        expected.y(),
+        expected.z(),
+        actual.x(),
+        actual.y(),
+        actual.z()
+    );
+}
+
+/// A hit on `material` facing a ray coming down at 45 degrees.
+fn hit_on(material: &dyn Material) -> (Ray, Hit<'_>) {
+    let ray = Ray::new(Point3::from([-1.0, 1.0, 0.0]), Vec3::from([1.0, -1.0, 0.0]));
+    let hit = Hit::new(
+        Point3::default(),
+        Vec3::from([0.0, 1.0, 0.0]),
+        1.0,
+        ray,
+        material,
+    );
+    (ray, hit)
+}
+
+#[test]
+fn vertices_ignore_weight_and_color() {
+    let model = parse(
+        "v 0 0 0 1\n\
+         v 1 0 0 1 0.5 0.2 0.1\n\
+         v 1 1 0 0.9 0.9 0.9\n\
+         v 0 1 0\n\
+         f 1 2 3 4\n",
+    )
+    .unwrap();
+
+    assert_eq!(model.triangle_count(), 2);
+}
+
+#[test]
+fn faces_are_grouped_by_group_and_material() {
+    let model = parse(
+        "v 0 0 0\nv 1 0 0\nv 0 1 0\n\
+         g first\n\
+         usemtl red\n\
+         f 1 2 3\n\
+         usemtl blue\n\
+         f 1 2 3\n\
+         f -3 -2 -1\n\
+         g second\n\
+         f 1 2 3\n",
+    )
+    .unwrap();
+
+    let groups: Vec<_> = model
+        .groups
+        .iter()
+        .map(|group| {
+            (
+                group.name.as_str(),
+                group.material.as_deref(),
+                group.mesh.triangle_count(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        groups,
+        [
+            ("first", Some("red"), 1),
+            ("first", Some("blue"), 2),
+            ("second", Some("blue"), 1)
+        ]
+    );
+}
+
+#[test]
+fn invalid_index_reports_its_line() {
+    let err = parse("v 0 0 0\nv 1 0 0\n# comment\nf 1 2 3\n")
+        .err()
+        .unwrap();
+
+    match err {
+        ObjError::Parse { line, message, .. } => {
+            assert_eq!(line, 4);
+            assert!(message.contains("out of range"), "{}", message);
+        }
+        ObjError::Io { .. } => panic!("unexpected I/O error"),
+    }
+}
+
+#[test]
+fn null_vertex_normals_keep_geometric_normal() {
+    let model = parse(
+        "v 0 0 0\nv 1 0 0\nv 0 1 0\n\
+         vn 0 0 0\n\
+         f 1//1 2//1 3//1\n",
+    )
+    .unwrap();
+    let mesh = &model.groups[0].mesh;
+
+    let ray = Ray::new(
+        Point3::from([0.25, 0.25, 1.0]),
+        Vec3::from([0.0, 0.0, -1.0]),
+    );
+    let hit = mesh.hit(ray, Interval::new(0.001, f64::INFINITY)).unwrap();
+
+    assert_near(hit.normal, Vec3::from([0.0, 0.0, 1.0]));
+}
+
+#[test]
+fn mtl_materials_map_onto_existing_ones() {
+    let materials = obj::parse_library(
+        "newmtl matte\n\
+         Kd 0.2 0.4 0.6\n\
+         \n\
+         newmtl mirror\n\
+         Kd 0.9 0.8 0.7\n\
+         Ns 1000\n\
+         illum 3\n\
+         \n\
+         newmtl glass\n\
+         d 0.5\n\
+         Ni 1.3\n\
+         \n\
+         newmtl lamp\n\
+         Kd 0.5 0.5 0.5\n\
+         Ke 4 3 2\n",
+        Path::new("test.mtl"),
+    )
+    .unwrap();
+    let mut sampler = Independent::new(0);
+
+    let (ray, hit) = hit_on(&*materials["matte"]);
+    assert_near(hit.material.albedo(&hit), color(0.2, 0.4, 0.6));
+    assert_near(hit.material.emitted(&hit), Color::default());
+    let (scattered, _) = hit.material.scatter(&ray, &hit, &mut sampler).unwrap();
+    assert!(dot(scattered.direction(), hit.normal) > 0.0);
+
+    // A mirror reflects the ray exactly.
+    let (ray, hit) = hit_on(&*materials["mirror"]);
+    let (scattered, attenuation) = hit.material.scatter(&ray, &hit, &mut sampler).unwrap();
+    assert_near(attenuation, color(0.9, 0.8, 0.7));
+    assert_near(
+        scattered.direction(),
+        Vec3::from([1.0, 1.0, 0.0]) / 2f64.sqrt(),
+    );
+
+    let (_, hit) = hit_on(&*materials["glass"]);
+    assert_near(hit.material.albedo(&hit), color(1.0, 1.0, 1.0));
+
+    let (_, hit) = hit_on(&*materials["lamp"]);
+    assert_near(hit.material.emitted(&hit), color(4.0, 3.0, 2.0));
+    assert!(hit.material.scatter(&ray, &hit, &mut sampler).is_none());
+}
+
+#[test]
+fn mtl_property_before_newmtl_is_an_error() {
+    let err = obj::parse_library("Kd 1 1 1\n", Path::new("test.mtl"))
+        .err()
+        .unwrap();
+
+    assert_eq!(err.to_string(), "test.mtl:1: `Kd` before any `newmtl`");
+}
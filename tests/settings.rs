@@ -0,0 +1,65 @@
+use raytracer::scene;
+use raytracer::{render, LinearBvh, Progressive, RenderSettings, SettingsError};
+
+fn render_two_spheres(settings: &RenderSettings) -> Result<(), SettingsError> {
+    let entry = scene::find("two_sphere").unwrap();
+    let world = LinearBvh::new((entry.build)(settings.seed));
+    let camera = (entry.camera)().aspect_ratio(settings.aspect_ratio).build();
+    render(&world, &(entry.environment)(), &camera, settings).map(|_| ())
+}
+
+fn small_settings() -> RenderSettings {
+    let mut settings = RenderSettings::new(8, 2.0);
+    settings.sample_per_pixel = 1;
+    settings.max_depth = 4;
+    settings
+}
+
+#[test]
+fn small_settings_render() {
+    render_two_spheres(&small_settings()).unwrap();
+}
+
+#[test]
+fn invalid_settings_are_rejected() {
+    let mut no_samples = small_settings();
+    no_samples.sample_per_pixel = 0;
+    let mut single_row = small_settings();
+    single_row.resize(8, 8.0);
+    let mut single_column = small_settings();
+    single_column.resize(1, 0.5);
+    let mut no_pass_samples = small_settings();
+    no_pass_samples.progressive = Some(Progressive {
+        pass_samples: 0,
+        time_budget: None,
+    });
+    let mut no_tile_size = small_settings();
+    no_tile_size.tile_size = 0;
+
+    assert!(matches!(
+        render_two_spheres(&no_samples),
+        Err(SettingsError::NoSamples)
+    ));
+    assert!(matches!(
+        render_two_spheres(&single_row),
+        Err(SettingsError::ImageTooSmall {
+            width: 8,
+            height: 1
+        })
+    ));
+    assert!(matches!(
+        render_two_spheres(&single_column),
+        Err(SettingsError::ImageTooSmall {
+            width: 1,
+            height: 2
+        })
+    ));
+    assert!(matches!(
+        render_two_spheres(&no_pass_samples),
+        Err(SettingsError::NoPassSamples)
+    ));
+    assert!(matches!(
+        render_two_spheres(&no_tile_size),
+        Err(SettingsError::NoTileSize)
+    ));
+}
@@ -0,0 +1,379 @@
+use raytracer::geometry::sphere::sphere_uv;
+use raytracer::hdr::HdrImage;
+use raytracer::hit::Footprint;
+use raytracer::material::lambertian::Lambertian;
+use raytracer::texture::checker_texture::CheckerTexture;
+use raytracer::texture::image_texture::{Filter, ImageTexture, Wrap};
+use raytracer::texture::mapping::{MappedTexture, Mapping};
+use raytracer::texture::solid_color::SolidColor;
+use raytracer::tonemap::srgb_decode;
+use raytracer::{Color, Hit, Point3, Ray, Texture, Vec3};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// A texture whose color is the coordinates it is looked up at.
+struct Coordinates;
+
+impl Texture for Coordinates {
+    fn value(&self, u: f64, v: f64, _p: Point3) -> Color {
+        Color::from([u, v, 0.0])
+    }
+}
+
+fn point(x: f64, y: f64, z: f64) -> Point3 {
+    Point3::from([x, y, z])
+}
+
+fn assert_uv((u, v): (f64, f64), expected: (f64, f64)) {
+    assert!(
+        (u - expected.0).abs() < 1e-9 && (v - expected.1).abs() < 1e-9,
+        "expected {:?}, found {:?}",
+        expected,
+        (u, v)
+    );
+}
+
+/// Coordinates given by `mapping` to the point `p` with surface coordinates
+/// `(u, v)`.
+fn mapped(mapping: Mapping, (u, v): (f64, f64), p: Point3) -> (f64, f64) {
+    let color = MappedTexture::new(Box::new(Coordinates), mapping).value(u, v, p);
+    (color.x(), color.y())
+}
+
+/// A path in the temporary directory, unique to this test process.
+fn temporary_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("raytracer-{}-{}", std::process::id(), name))
+}
+
+/// A texture of a single row of pixels whose values are their index.
+fn ramp(width: usize, wrap: Wrap, filter: Filter) -> ImageTexture {
+    let mut image = HdrImage::new(width, 1);
+    for x in 0..width {
+        let value = x as f64;
+        image.set_pixel(x, 0, Color::from([value, value, value]));
+    }
+    ImageTexture::new(Arc::new(image))
+        .wrapped(wrap)
+        .filtered(filter)
+}
+
+/// Value of `texture` at the center of the pixel `x` of a row of `width`
+/// pixels, `x` being possibly outside of the image.
+fn at_pixel(texture: &ImageTexture, x: i64, width: usize) -> f64 {
+    let u = (x as f64 + 0.5) / width as f64;
+    texture.value(u, 0.5, Point3::default()).x()
+}
+
+/// Color of `texture` at a hit at `p` with the coordinates `(u, v)`, seen
+/// by a pixel covering `footprint`.
+fn value_with_footprint(
+    texture: &dyn Texture,
+    (u, v): (f64, f64),
+    p: Point3,
+    footprint: Footprint,
+) -> Color {
+    let material = Lambertian::new(Color::default());
+    let normal = Vec3::from([0.0, 0.0, 1.0]);
+    let hit = Hit {
+        u,
+        v,
+        footprint,
+        ..Hit::new(p, normal, 1.0, Ray::new(p + normal, -normal), &material)
+    };
+    texture.value_at(&hit)
+}
+
+#[test]
+fn empty_image_is_rejected_at_load_time() {
+    let path = temporary_path("empty.hdr");
+    fs::write(&path, "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 4 +X 0\n").unwrap();
+    let result = ImageTexture::load(&path);
+    fs::remove_file(&path).unwrap();
+
+    let message = result.err().unwrap().to_string();
+    assert!(message.contains("empty 0x4 image"), "{}", message);
+}
+
+#[test]
+fn sphere_uv_follows_longitude_and_latitude() {
+    assert_uv(sphere_uv(point(1.0, 0.0, 0.0)), (0.5, 0.5));
+    assert_uv(sphere_uv(point(0.0, 0.0, 1.0)), (0.25, 0.5));
+    assert_uv(sphere_uv(point(0.0, 0.0, -1.0)), (0.75, 0.5));
+    assert_uv(sphere_uv(point(-1.0, 0.0, 0.0)), (0.0, 0.5));
+}
+
+#[test]
+fn sphere_uv_is_defined_at_the_poles_and_the_seam() {
+    assert_uv(sphere_uv(point(0.0, 1.0, 0.0)), (0.5, 1.0));
+    assert_uv(sphere_uv(point(0.0, -1.0, 0.0)), (0.5, 0.0));
+    // Rounding may push the direction slightly out of the unit sphere.
+    let (_, v) = sphere_uv(point(0.0, 1.0 + 1e-12, 0.0));
+    assert_eq!(v, 1.0);
+
+    // Either side of the -X seam, `u` goes from 1 back to 0.
+    let (before, _) = sphere_uv(point(-1.0, 0.0, -1e-6));
+    let (after, _) = sphere_uv(point(-1.0, 0.0, 1e-6));
+    assert!(before > 1.0 - 1e-6 && before <= 1.0, "{}", before);
+    assert!((0.0..1e-6).contains(&after), "{}", after);
+}
+
+#[test]
+fn mappings_project_the_hit_point() {
+    let p = point(2.0, 5.0, 2.0);
+    assert_uv(mapped(Mapping::Uv, (0.3, 0.7), p), (0.3, 0.7));
+    let planar = Mapping::Planar {
+        origin: point(1.0, 0.0, 0.0),
+        u: Vec3::from([2.0, 0.0, 0.0]),
+        v: Vec3::from([0.0, 0.0, 4.0]),
+    };
+    assert_uv(mapped(planar, (0.3, 0.7), p), (0.5, 0.5));
+
+    let cylindrical = Mapping::Cylindrical {
+        center: Point3::default(),
+        axis: Vec3::from([0.0, 2.0, 0.0]),
+    };
+    assert_uv(
+        mapped(cylindrical, (0.0, 0.0), point(0.0, 1.0, 1.0)),
+        (0.5, 0.5),
+    );
+    assert_uv(
+        mapped(cylindrical, (0.0, 0.0), point(1.0, 0.0, 0.0)),
+        (0.75, 0.0),
+    );
+
+    let spherical = Mapping::Spherical {
+        center: point(1.0, 1.0, 1.0),
+    };
+    assert_uv(
+        mapped(spherical, (0.0, 0.0), point(1.0, 3.0, 1.0)),
+        (0.5, 1.0),
+    );
+    assert_uv(
+        mapped(spherical, (0.0, 0.0), point(2.0, 1.0, 1.0)),
+        (0.5, 0.5),
+    );
+}
+
+#[test]
+fn coordinates_are_scaled_then_rotated_then_offset() {
+    let texture = MappedTexture::new(Box::new(Coordinates), Mapping::Uv)
+        .scaled(2.0, 3.0)
+        .rotated(90.0)
+        .offset(0.5, 0.0);
+
+    let color = texture.value(0.1, 0.2, Point3::default());
+
+    assert_uv((color.x(), color.y()), (-0.1, 0.2));
+}
+
+#[test]
+fn triplanar_blends_projections_by_the_normal() {
+    let material = Lambertian::new(Color::from([0.5, 0.5, 0.5]));
+    let hit_with = |normal: Vec3| {
+        let p = point(1.0, 2.0, 3.0);
+        Hit::new(p, normal, 1.0, Ray::new(p + normal, -normal), &material)
+    };
+    let white = MappedTexture::new(
+        Box::new(SolidColor::from_rgb(1.0, 1.0, 1.0)),
+        Mapping::Triplanar { sharpness: 4.0 },
+    );
+    let coordinates =
+        MappedTexture::new(Box::new(Coordinates), Mapping::Triplanar { sharpness: 1.0 });
+
+    // The weights sum to 1 whatever the normal.
+    for normal in [
+        Vec3::from([0.6, 0.8, 0.0]),
+        Vec3::from([-0.48, 0.6, -0.64]),
+        Vec3::from([0.0, 0.0, 1.0]),
+    ] {
+        let color = white.value_at(&hit_with(normal));
+        assert_uv((color.x(), color.z()), (1.0, 1.0));
+    }
+
+    // Along an axis, only the projection onto the perpendicular plane counts.
+    let along_x = coordinates.value_at(&hit_with(Vec3::from([1.0, 0.0, 0.0])));
+    assert_uv((along_x.x(), along_x.y()), (3.0, 2.0));
+    let along_y = coordinates.value_at(&hit_with(Vec3::from([0.0, -1.0, 0.0])));
+    assert_uv((along_y.x(), along_y.y()), (1.0, 3.0));
+    let along_z = coordinates.value_at(&hit_with(Vec3::from([0.0, 0.0, 1.0])));
+    assert_uv((along_z.x(), along_z.y()), (1.0, 2.0));
+    // Halfway between the X and Z axes, both projections count as much.
+    let diagonal = coordinates.value_at(&hit_with(Vec3::from([0.6, 0.0, 0.6])));
+    assert_uv((diagonal.x(), diagonal.y()), (2.0, 2.0));
+}
+
+#[test]
+fn wrap_modes_bring_pixels_back_onto_the_image() {
+    let expected = [
+        (Wrap::Repeat, [3.0, 0.0, 0.0]),
+        (Wrap::Clamp, [0.0, 3.0, 3.0]),
+        (Wrap::Mirror, [0.0, 3.0, 0.0]),
+    ];
+
+    for (wrap, values) in expected {
+        let texture = ramp(4, wrap, Filter::Nearest);
+        for (x, value) in [-1, 4, 8].into_iter().zip(values) {
+            assert_eq!(at_pixel(&texture, x, 4), value, "{} at {}", wrap, x);
+        }
+    }
+}
+
+#[test]
+fn filters_interpolate_between_pixels() {
+    let nearest = ramp(4, Wrap::Clamp, Filter::Nearest);
+    let bilinear = ramp(4, Wrap::Clamp, Filter::Bilinear);
+    let bicubic = ramp(4, Wrap::Clamp, Filter::Bicubic);
+    let at = |texture: &ImageTexture, x: f64| texture.value(x / 4.0, 0.5, Point3::default()).x();
+
+    // Pixel centers are at half integers.
+    assert_eq!(at(&nearest, 1.4), 1.0);
+    assert_eq!(at(&nearest, 1.6), 1.0);
+    assert_eq!(at(&nearest, 2.6), 2.0);
+    for filter in [&bilinear, &bicubic] {
+        assert_uv((at(filter, 1.5), at(filter, 2.0)), (1.0, 1.5));
+        // Both reproduce a linear ramp away from the clamped edges.
+        assert_uv((at(filter, 1.75), at(filter, 2.25)), (1.25, 1.75));
+    }
+}
+
+#[test]
+fn bicubic_weights_sum_to_one() {
+    let mut image = HdrImage::new(5, 3);
+    for y in 0..3 {
+        for x in 0..5 {
+            image.set_pixel(x, y, Color::from([0.7, 0.2, 0.1]));
+        }
+    }
+    let texture = ImageTexture::new(Arc::new(image)).filtered(Filter::Bicubic);
+
+    for (u, v) in [(0.1, 0.2), (0.33, 0.9), (0.5, 0.5), (0.77, 0.05)] {
+        let color = texture.value(u, v, Point3::default());
+        assert_uv((color.x(), color.y()), (0.7, 0.2));
+        assert!((color.z() - 0.1).abs() < 1e-9, "{}", color.z());
+    }
+}
+
+#[test]
+fn png_texels_are_decoded_from_srgb() {
+    let path = temporary_path("srgb.png");
+    let bytes = [0, 128, 255, 188, 10, 64];
+    let mut encoder = png::Encoder::new(fs::File::create(&path).unwrap(), 2, 1);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .unwrap()
+        .write_image_data(&bytes)
+        .unwrap();
+    let texture = ImageTexture::load(&path).map(|texture| texture.filtered(Filter::Nearest));
+    fs::remove_file(&path).unwrap();
+    let texture = texture.unwrap();
+
+    for (x, texel) in bytes.chunks(3).enumerate() {
+        let color = texture.value((x as f64 + 0.5) / 2.0, 0.5, Point3::default());
+        for (actual, byte) in [color.x(), color.y(), color.z()].into_iter().zip(texel) {
+            assert!((actual - srgb_decode(*byte as f64 / 255.0)).abs() < 1e-12);
+        }
+    }
+    let first = texture.value(0.25, 0.5, Point3::default());
+    assert_eq!((first.x(), first.z()), (0.0, 1.0));
+    assert!((first.y() - 0.2158605).abs() < 1e-6, "{}", first.y());
+}
+
+#[test]
+fn checker_without_footprint_matches_its_value() {
+    let texture = CheckerTexture::from_color(
+        0.5,
+        Color::from([1.0, 0.0, 0.0]),
+        Color::from([0.0, 0.0, 1.0]),
+    );
+
+    for p in [
+        point(0.1, 0.2, 0.3),
+        point(0.6, 0.2, 0.3),
+        point(-0.1, 0.7, 2.3),
+        point(-1.6, -0.2, -0.8),
+    ] {
+        let filtered = value_with_footprint(&texture, (0.0, 0.0), p, Footprint::default());
+        let point = texture.value(0.0, 0.0, p);
+        assert_eq!(
+            (filtered.x(), filtered.z()),
+            (point.x(), point.z()),
+            "at ({}, {}, {})",
+            p.x(),
+            p.y(),
+            p.z()
+        );
+    }
+}
+
+#[test]
+fn checker_under_a_wide_footprint_blends_both_colors_equally() {
+    let texture = CheckerTexture::from_color(
+        0.5,
+        Color::from([1.0, 0.0, 0.0]),
+        Color::from([0.0, 0.0, 1.0]),
+    );
+    // The footprint covers a hundred cells along X.
+    let footprint = Footprint {
+        dpdx: Vec3::from([50.0, 0.0, 0.0]),
+        dpdy: Vec3::from([0.0, 0.01, 0.0]),
+        ..Footprint::default()
+    };
+
+    for p in [
+        point(0.1, 0.2, 0.3),
+        point(0.6, 0.2, 0.3),
+        point(-1.3, 4.2, 0.9),
+    ] {
+        let color = value_with_footprint(&texture, (0.0, 0.0), p, footprint);
+        assert!((color.x() - 0.5).abs() < 0.01, "{}", color.x());
+        assert!((color.z() - 0.5).abs() < 0.01, "{}", color.z());
+    }
+}
+
+#[test]
+fn mip_level_follows_the_footprint_size() {
+    // Blocks of 2x2 pixels at 0.2, 0.4, 0.6 and 0.8, with a checkerboard of
+    // +0.1 and -0.1 that averages out in the first reduction. The reductions
+    // are then the blocks, and their average, 0.5.
+    let mut image = HdrImage::new(4, 4);
+    for y in 0..4 {
+        for x in 0..4 {
+            let block = 0.2 * (1 + x / 2 + 2 * (y / 2)) as f64;
+            let detail = if (x + y) % 2 == 0 { 0.1 } else { -0.1 };
+            let value = block + detail;
+            image.set_pixel(x, y, Color::from([value, value, value]));
+        }
+    }
+    let texture = ImageTexture::new(Arc::new(image)).filtered(Filter::Nearest);
+    // The center of the top left pixel, at 0.3.
+    let uv = (0.125, 0.875);
+    let at_size = |texture: &ImageTexture, pixels: f64| {
+        let footprint = Footprint {
+            dudx: pixels / 4.0,
+            dvdy: 0.1 / 4.0,
+            ..Footprint::default()
+        };
+        value_with_footprint(texture, uv, Point3::default(), footprint).x()
+    };
+
+    assert_uv((at_size(&texture, 0.5), at_size(&texture, 1.0)), (0.3, 0.3));
+    assert_uv((at_size(&texture, 2.0), at_size(&texture, 4.0)), (0.2, 0.5));
+    // Between two levels, the lookups are blended.
+    let t = 3f64.log2() - 1.0;
+    assert_uv(
+        (at_size(&texture, 3.0), at_size(&texture, 100.0)),
+        ((1.0 - t) * 0.2 + t * 0.5, 0.5),
+    );
+    let full_resolution = texture.without_mipmaps();
+    assert_uv(
+        (
+            at_size(&full_resolution, 4.0),
+            at_size(&full_resolution, 100.0),
+        ),
+        (0.3, 0.3),
+    );
+}
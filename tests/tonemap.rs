@@ -0,0 +1,108 @@
+use raytracer::tonemap::{srgb_decode, srgb_encode};
+use raytracer::{Color, OutputSettings, ToneMap};
+
+fn color(r: f64, g: f64, b: f64) -> Color {
+    Color::from([r, g, b])
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!(
+        (actual - expected).abs() < 1e-9,
+        "expected {}, found {}",
+        expected,
+        actual
+    );
+}
+
+fn assert_color(actual: Color, expected: Color) {
+    for (a, e) in [
+        (actual.x(), expected.x()),
+        (actual.y(), expected.y()),
+        (actual.z(), expected.z()),
+    ] {
+        assert_close(a, e);
+    }
+}
+
+#[test]
+fn clamp_cuts_outside_the_unit_range() {
+    let mapped = ToneMap::Clamp.apply(color(-0.5, 0.25, 4.0));
+
+    assert_color(mapped, color(0.0, 0.25, 1.0));
+}
+
+#[test]
+fn reinhard_compresses_the_luminance_and_keeps_the_hue() {
+    assert_color(
+        ToneMap::Reinhard.apply(color(1.0, 1.0, 1.0)),
+        color(0.5, 0.5, 0.5),
+    );
+    assert_color(ToneMap::Reinhard.apply(Color::default()), Color::default());
+
+    let original = color(0.8, 0.4, 0.2);
+    let mapped = ToneMap::Reinhard.apply(original);
+    let scale = mapped.x() / original.x();
+    assert!(scale < 1.0);
+    assert_color(mapped, scale * original);
+}
+
+#[test]
+fn aces_follows_the_filmic_curve() {
+    let mapped = ToneMap::Aces.apply(color(0.0, 1.0, 100.0));
+
+    assert_close(mapped.x(), 0.0);
+    assert_close(mapped.y(), 2.54 / 3.16);
+    // The curve tends to 2.51 / 2.43 and is clamped to 1.
+    assert_close(mapped.z(), 1.0);
+}
+
+#[test]
+fn srgb_transfer_round_trips() {
+    for i in 0..=100 {
+        let linear = i as f64 / 100.0;
+        assert_close(srgb_decode(srgb_encode(linear)), linear);
+    }
+}
+
+#[test]
+fn srgb_transfer_is_continuous_at_the_linear_segment() {
+    let (linear, encoded) = (0.003_130_8, 0.040_45);
+
+    assert_close(srgb_encode(linear), 12.92 * linear);
+    assert!((1.055 * linear.powf(1.0 / 2.4) - 0.055 - srgb_encode(linear)).abs() < 1e-6);
+    assert!((srgb_encode(linear) - encoded).abs() < 1e-6);
+    assert_close(srgb_decode(encoded), encoded / 12.92);
+    assert!((((encoded + 0.055) / 1.055).powf(2.4) - srgb_decode(encoded)).abs() < 1e-6);
+}
+
+#[test]
+fn encode_quantizes_to_the_nearest_byte() {
+    let output = OutputSettings::default();
+
+    assert_eq!(output.encode(color(0.0, 1.0, 4.0)), [0, 255, 255]);
+    // Negative and undefined values end up black.
+    assert_eq!(output.encode(color(-1.0, f64::NAN, 0.0)), [0, 0, 0]);
+    // Just below and just above the boundary between bytes 127 and 128.
+    let half = srgb_decode(127.5 / 255.0);
+    assert_eq!(
+        output.encode(color(0.999 * half, 1.001 * half, 0.0)),
+        [127, 128, 0]
+    );
+}
+
+#[test]
+fn exposure_doubles_the_brightness_per_stop() {
+    let reference = OutputSettings::default();
+    let brighter = OutputSettings {
+        exposure: 1.0,
+        ..reference
+    };
+    let darker = OutputSettings {
+        exposure: -2.0,
+        ..reference
+    };
+    let linear = color(0.1, 0.2, 0.4);
+
+    assert_eq!(brighter.encode(linear), reference.encode(2.0 * linear));
+    assert_eq!(darker.encode(linear), reference.encode(0.25 * linear));
+}